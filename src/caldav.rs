@@ -0,0 +1,721 @@
+//! Armazenamento sincronizado com uma coleção de tarefas CalDAV (VTODO).
+//!
+//! O arquivo local continua sendo a fonte de leitura: cada alteração é gravada nele
+//! e depois enviada ao servidor. O estado da sincronização (href, ETag e a última
+//! versão enviada de cada TODO) fica em `<arquivo>.caldav`, então tudo que ainda não
+//! chegou ao servidor é a fila de alterações pendentes, que sobrevive a reinícios e
+//! é reenviada quando o servidor volta a responder.
+
+use crate::{
+    cli::AppError,
+    formats::ical,
+    todo::{Bulk, Position, Todo, TodoList, TodoStorage, Todos},
+    tracking::Report,
+};
+use chrono::NaiveDate;
+use reqwest::{header, Client, Method, StatusCode, Url};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    env,
+    time::Duration,
+};
+
+const REPORT_BODY: &str = r#"<?xml version="1.0" encoding="utf-8" ?>
+<C:calendar-query xmlns:D="DAV:" xmlns:C="urn:ietf:params:xml:ns:caldav">
+  <D:prop><D:getetag/><C:calendar-data/></D:prop>
+  <C:filter>
+    <C:comp-filter name="VCALENDAR"><C:comp-filter name="VTODO"/></C:comp-filter>
+  </C:filter>
+</C:calendar-query>"#;
+
+/// O estado da sincronização fica em `<arquivo>.caldav`.
+const STATE_EXTENSION: &str = "caldav";
+
+pub struct CalDavConfig {
+    pub url: String,
+    pub user: Option<String>,
+    pub password: Option<String>,
+}
+
+impl CalDavConfig {
+    /// Lê `TODO_CALDAV_URL` (a coleção, terminada em `/`), `TODO_CALDAV_USER` e
+    /// `TODO_CALDAV_PASSWORD`. Sem a URL o armazenamento fica só no arquivo local.
+    pub fn from_env() -> Option<Self> {
+        Some(Self {
+            url: env::var("TODO_CALDAV_URL").ok()?,
+            user: env::var("TODO_CALDAV_USER").ok(),
+            password: env::var("TODO_CALDAV_PASSWORD").ok(),
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct Remote {
+    href: String,
+    etag: Option<String>,
+    /// Versão do TODO que o servidor conhece.
+    synced: Option<Todo>,
+    /// Conteúdo do recurso quando o servidor não informa o ETag, para saber se ele
+    /// mudou desde a última leitura.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    data: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct SyncState {
+    remote: BTreeMap<u32, Remote>,
+}
+
+enum Change {
+    Put(u32),
+    Delete(u32),
+}
+
+enum Failure {
+    Offline,
+    Error(AppError),
+}
+
+impl From<reqwest::Error> for Failure {
+    fn from(err: reqwest::Error) -> Self {
+        if err.is_connect() || err.is_timeout() || err.is_request() {
+            Self::Offline
+        } else {
+            Self::Error(AppError::Sync(err.to_string()))
+        }
+    }
+}
+
+struct CalDavClient {
+    client: Client,
+    collection: Url,
+    config: CalDavConfig,
+}
+
+impl CalDavClient {
+    fn new(config: CalDavConfig) -> Result<Self, AppError> {
+        let collection = Url::parse(&config.url).map_err(|err| AppError::Sync(err.to_string()))?;
+        let client = Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()
+            .map_err(|err| AppError::Sync(err.to_string()))?;
+        Ok(Self {
+            client,
+            collection,
+            config,
+        })
+    }
+
+    fn url(&self, href: &str) -> Result<Url, Failure> {
+        self.collection
+            .join(href)
+            .map_err(|err| Failure::Error(AppError::Sync(err.to_string())))
+    }
+
+    /// Compara hrefs relativos e absolutos pela URL final.
+    fn same(&self, href: &str, other: &str) -> bool {
+        match (self.url(href), self.url(other)) {
+            (Ok(url), Ok(other)) => url == other,
+            _ => false,
+        }
+    }
+
+    async fn send(
+        &self,
+        method: Method,
+        href: &str,
+        headers: &[(header::HeaderName, &str)],
+        body: String,
+    ) -> Result<reqwest::Response, Failure> {
+        let mut request = self.client.request(method, self.url(href)?).body(body);
+        for (name, value) in headers {
+            request = request.header(name, *value);
+        }
+        if let Some(user) = &self.config.user {
+            request = request.basic_auth(user, self.config.password.as_ref());
+        }
+        let response = request.send().await?;
+        if response.status().is_server_error() {
+            return Err(Failure::Offline);
+        }
+        Ok(response)
+    }
+
+    /// Lista `(href, etag, calendar-data)` de todos os VTODOs da coleção.
+    async fn report(&self) -> Result<Vec<(String, Option<String>, String)>, Failure> {
+        let report = Method::from_bytes(b"REPORT").expect("REPORT é um método válido");
+        let response = self
+            .send(
+                report,
+                "",
+                &[
+                    (header::CONTENT_TYPE, "application/xml; charset=utf-8"),
+                    (header::HeaderName::from_static("depth"), "1"),
+                ],
+                REPORT_BODY.to_string(),
+            )
+            .await?;
+        let body = expect_success(response).await?.text().await?;
+        Ok(elements(&body, "response")
+            .into_iter()
+            .filter_map(|response| {
+                let href = elements(response, "href")
+                    .first()
+                    .map(|href| unescape(href))?;
+                let etag = elements(response, "getetag")
+                    .first()
+                    .map(|etag| unescape(etag));
+                let data = elements(response, "calendar-data")
+                    .first()
+                    .map(|data| unescape(data))?;
+                Some((href, etag, data))
+            })
+            .collect())
+    }
+
+    /// O ETag atual do recurso; `None` se ele não existe no servidor e `Some(None)`
+    /// se o servidor não informa o ETag.
+    async fn etag(&self, href: &str) -> Result<Option<Option<String>>, Failure> {
+        let response = self.send(Method::GET, href, &[], String::new()).await?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        Ok(Some(etag_header(&expect_success(response).await?)))
+    }
+}
+
+pub struct CalDavStorage {
+    todos: Todos,
+    client: CalDavClient,
+    state: SyncState,
+    conflicts: BTreeSet<u32>,
+}
+
+impl CalDavStorage {
+    pub async fn new(todos: Todos, config: CalDavConfig) -> Result<Self, AppError> {
+        let state = match todos.read_side(STATE_EXTENSION).await? {
+            Some(contents) => serde_json::from_str(&contents).map_err(AppError::Parse)?,
+            None => SyncState::default(),
+        };
+        let mut storage = Self {
+            todos,
+            client: CalDavClient::new(config)?,
+            state,
+            conflicts: BTreeSet::new(),
+        };
+        // conflitos encontrados aqui serão apresentados na próxima alteração
+        match storage.sync().await {
+            Ok(_) | Err(AppError::Conflict) => Ok(storage),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Envia as alterações pendentes e depois busca as do servidor.
+    async fn sync(&mut self) -> Result<bool, AppError> {
+        self.push().await?;
+        self.pull(&BTreeSet::new()).await
+    }
+
+    fn pending(&self) -> Vec<Change> {
+        let ids: BTreeSet<u32> = self
+            .state
+            .remote
+            .keys()
+            .copied()
+            .chain(self.todos.all().iter().map(|todo| todo.id))
+            .collect();
+        ids.into_iter()
+            .filter(|id| self.is_pending(*id))
+            .map(|id| match self.local(id) {
+                Some(_) => Change::Put(id),
+                None => Change::Delete(id),
+            })
+            .collect()
+    }
+
+    /// Só conta o que vai no VTODO: o tempo registrado, os pomodoros, a lista e a
+    /// ordem ficam no arquivo local e não geram envios.
+    fn is_pending(&self, id: u32) -> bool {
+        let synced = self
+            .state
+            .remote
+            .get(&id)
+            .and_then(|remote| remote.synced.as_ref());
+        match (synced, self.local(id)) {
+            (Some(synced), Some(local)) => !ical::same(synced, local),
+            (synced, local) => synced.is_some() != local.is_some(),
+        }
+    }
+
+    /// O recurso do TODO no servidor: o já conhecido ou, para um TODO novo, `<uuid>.ics`.
+    fn href(&self, id: u32) -> Option<String> {
+        match self.state.remote.get(&id) {
+            Some(remote) => Some(remote.href.clone()),
+            None => self.local(id).map(|todo| format!("{}.ics", todo.uuid)),
+        }
+    }
+
+    /// O TODO local como ele deve estar no servidor: os que estão na lixeira não existem lá.
+    fn local(&self, id: u32) -> Option<&Todo> {
+        self.todos.get(id).filter(|todo| todo.deleted.is_none())
+    }
+
+    async fn push(&mut self) -> Result<(), AppError> {
+        for change in self.pending() {
+            let result = match change {
+                Change::Put(id) => self.put(id).await,
+                Change::Delete(id) => self.remove(id).await,
+            };
+            match result {
+                Ok(()) => (),
+                Err(Failure::Offline) => break, //fica na fila até o servidor voltar
+                Err(Failure::Error(err)) => {
+                    self.save_state().await?;
+                    return Err(err);
+                }
+            }
+        }
+        self.save_state().await?;
+        if self.conflicts.is_empty() {
+            Ok(())
+        } else {
+            Err(AppError::Conflict)
+        }
+    }
+
+    async fn put(&mut self, id: u32) -> Result<(), Failure> {
+        let Some(todo) = self.local(id).cloned() else {
+            return Ok(());
+        };
+        let Some(href) = self.href(id) else {
+            return Ok(());
+        };
+        let remote = self.state.remote.get(&id).cloned();
+        let known = remote.is_some();
+        let etag = remote.and_then(|remote| remote.etag);
+        let mut headers = vec![(header::CONTENT_TYPE, "text/calendar; charset=utf-8")];
+        match &etag {
+            Some(etag) => headers.push((header::IF_MATCH, etag.as_str())),
+            // um recurso que existe mas sem ETag não tem como ser conferido
+            None if known => (),
+            None => headers.push((header::IF_NONE_MATCH, "*")),
+        }
+        let response = self
+            .client
+            .send(Method::PUT, &href, &headers, ical::export(&[&todo]))
+            .await?;
+        if response.status() == StatusCode::PRECONDITION_FAILED {
+            self.conflicts.insert(id);
+            return Ok(());
+        }
+        let response = expect_success(response).await?;
+        self.state.remote.insert(
+            id,
+            Remote {
+                href,
+                etag: etag_header(&response),
+                synced: Some(todo),
+                data: None,
+            },
+        );
+        Ok(())
+    }
+
+    async fn remove(&mut self, id: u32) -> Result<(), Failure> {
+        let Some(remote) = self.state.remote.get(&id).cloned() else {
+            return Ok(());
+        };
+        let headers: Vec<(header::HeaderName, &str)> = match &remote.etag {
+            Some(etag) => vec![(header::IF_MATCH, etag.as_str())],
+            None => Vec::new(),
+        };
+        let response = self
+            .client
+            .send(Method::DELETE, &remote.href, &headers, String::new())
+            .await?;
+        match response.status() {
+            StatusCode::PRECONDITION_FAILED => {
+                self.conflicts.insert(id);
+            }
+            StatusCode::NOT_FOUND => {
+                self.state.remote.remove(&id);
+            }
+            _ => {
+                expect_success(response).await?;
+                self.state.remote.remove(&id);
+            }
+        }
+        Ok(())
+    }
+
+    /// Traz as alterações do servidor. TODOs com alteração local pendente só são
+    /// sobrescritos quando estão em `force`. Devolve se a lista local mudou.
+    async fn pull(&mut self, force: &BTreeSet<u32>) -> Result<bool, AppError> {
+        let items = match self.client.report().await {
+            Ok(items) => items,
+            Err(Failure::Offline) => return Ok(false),
+            Err(Failure::Error(err)) => return Err(err),
+        };
+
+        let mut seen = BTreeSet::new();
+        let mut updates = Vec::new();
+        for (href, etag, data) in items {
+            let known = self
+                .state
+                .remote
+                .iter()
+                .find(|(_, remote)| self.client.same(&remote.href, &href))
+                .map(|(id, remote)| (*id, remote.clone()));
+            if let Some((id, _)) = known {
+                seen.insert(id);
+            }
+            // um recurso conhecido é sempre o mesmo TODO, qualquer que seja o UID dele
+            let todos = match known.as_ref().and_then(|(id, _)| self.todos.get(*id)) {
+                Some(local) => ical::update(&data, local).map(|todo| vec![todo]),
+                None => ical::import(&data, &self.todos.all()).map(|import| import.todos),
+            };
+            let Ok(todos) = todos else {
+                continue;
+            };
+            for mut todo in todos {
+                let id = known.as_ref().map(|(id, _)| *id).unwrap_or(todo.id);
+                if id != 0 {
+                    seen.insert(id);
+                    let unchanged = known.as_ref().is_some_and(|(_, known)| {
+                        match (&known.etag, &etag) {
+                            (Some(known), Some(etag)) => known == etag,
+                            // sem ETag, vale o conteúdo do recurso
+                            _ => known.data.as_ref() == Some(&data),
+                        }
+                    });
+                    if unchanged || (self.is_pending(id) && !force.contains(&id)) {
+                        continue;
+                    }
+                    todo.id = id;
+                }
+                let data = etag.is_none().then(|| data.clone());
+                updates.push((
+                    todo,
+                    Remote {
+                        href: href.clone(),
+                        etag: etag.clone(),
+                        synced: None,
+                        data,
+                    },
+                ));
+            }
+        }
+
+        let deleted: Vec<u32> = self
+            .state
+            .remote
+            .keys()
+            .filter(|id| !seen.contains(id) && (!self.is_pending(**id) || force.contains(id)))
+            .copied()
+            .collect();
+        let changed = !updates.is_empty() || !deleted.is_empty();
+
+        for id in deleted {
+            self.state.remote.remove(&id);
+            self.todos.delete(id).await?;
+        }
+        let (todos, remotes): (Vec<Todo>, Vec<Remote>) = updates.into_iter().unzip();
+        if !todos.is_empty() {
+            let ids = self.todos.upsert(todos).await?;
+            for (id, remote) in ids.into_iter().zip(remotes) {
+                let synced = self.local(id).cloned();
+                self.state.remote.insert(id, Remote { synced, ..remote });
+            }
+        }
+        self.save_state().await?;
+        Ok(changed)
+    }
+
+    async fn save_state(&self) -> Result<(), AppError> {
+        let contents = serde_json::to_string(&self.state).map_err(AppError::Parse)?;
+        self.todos.write_side(STATE_EXTENSION, &contents).await
+    }
+}
+
+#[async_trait::async_trait]
+impl TodoStorage for CalDavStorage {
+    async fn add(&mut self, message: String) -> Result<Option<&Todo>, AppError> {
+        let id = self.todos.add(message).await?.map(|todo| todo.id);
+        self.push().await?;
+        Ok(id.and_then(|id| self.todos.get(id)))
+    }
+
+    async fn list(&self) -> Result<Vec<&Todo>, AppError> {
+        self.todos.list().await
+    }
+
+    async fn find(&self, key: &str) -> Result<Option<&Todo>, AppError> {
+        self.todos.find(key).await
+    }
+
+    async fn locate(&self, key: &str) -> Result<Option<&Todo>, AppError> {
+        self.todos.locate(key).await
+    }
+
+    async fn update(&mut self, id: u32, message: String) -> Result<Option<&Todo>, AppError> {
+        self.todos.update(id, message).await?;
+        self.push().await?;
+        Ok(self.todos.get(id))
+    }
+
+    async fn done(&mut self, id: u32) -> Result<Option<&Todo>, AppError> {
+        self.todos.done(id).await?;
+        self.push().await?;
+        Ok(self.todos.get(id))
+    }
+
+    async fn delete(&mut self, id: u32) -> Result<Option<Todo>, AppError> {
+        let todo = self.todos.delete(id).await?;
+        self.push().await?;
+        Ok(todo)
+    }
+
+    async fn update_notes(&mut self, id: u32, notes: String) -> Result<Option<&Todo>, AppError> {
+        self.todos.update_notes(id, notes).await?;
+        self.push().await?;
+        Ok(self.todos.get(id))
+    }
+
+    /// Descarta as alterações locais em conflito, ficando com a versão do servidor.
+    async fn reload(&mut self) -> Result<(), AppError> {
+        self.todos.reload().await?;
+        let force = std::mem::take(&mut self.conflicts);
+        self.pull(&force).await?;
+        Ok(())
+    }
+
+    /// Mantém a versão local dos TODOs em conflito, sobrescrevendo a do servidor. Um
+    /// TODO que mudou de novo no servidor nesse meio tempo continua em conflito e é
+    /// apresentado na próxima alteração.
+    async fn merge(&mut self) -> Result<Vec<u32>, AppError> {
+        let mut conflicts = self.todos.merge().await?;
+        for id in std::mem::take(&mut self.conflicts) {
+            let Some(href) = self.href(id) else {
+                continue;
+            };
+            let synced = self
+                .state
+                .remote
+                .get(&id)
+                .and_then(|remote| remote.synced.clone());
+            match self.client.etag(&href).await {
+                Ok(Some(etag)) => {
+                    let remote = Remote {
+                        href,
+                        etag,
+                        synced,
+                        data: None,
+                    };
+                    self.state.remote.insert(id, remote);
+                }
+                // sumiu do servidor: volta a ser criado
+                Ok(None) => {
+                    self.state.remote.remove(&id);
+                }
+                Err(Failure::Offline) => continue,
+                Err(Failure::Error(err)) => return Err(err),
+            }
+            conflicts.push(id);
+        }
+        match self.push().await {
+            Ok(()) | Err(AppError::Conflict) => (),
+            Err(err) => return Err(err),
+        }
+        conflicts.extend(&self.conflicts);
+        conflicts.sort_unstable();
+        conflicts.dedup();
+        Ok(conflicts)
+    }
+
+    async fn refresh(&mut self) -> Result<bool, AppError> {
+        let changed = self.todos.refresh().await?;
+        Ok(self.sync().await? || changed)
+    }
+
+    async fn import(&mut self, todos: Vec<Todo>) -> Result<usize, AppError> {
+        let count = self.todos.import(todos).await?;
+        self.push().await?;
+        Ok(count)
+    }
+
+    async fn lists(&self) -> Result<Vec<TodoList>, AppError> {
+        self.todos.lists().await
+    }
+
+    async fn current_list(&self) -> Result<String, AppError> {
+        self.todos.current_list().await
+    }
+
+    async fn switch_list(&mut self, name: &str) -> Result<bool, AppError> {
+        self.todos.switch_list(name).await
+    }
+
+    async fn create_list(&mut self, name: &str) -> Result<bool, AppError> {
+        self.todos.create_list(name).await
+    }
+
+    async fn rename_list(&mut self, name: &str, new_name: &str) -> Result<bool, AppError> {
+        let renamed = self.todos.rename_list(name, new_name).await?;
+        self.push().await?;
+        Ok(renamed)
+    }
+
+    async fn archive_list(&mut self, name: &str, archived: bool) -> Result<bool, AppError> {
+        self.todos.archive_list(name, archived).await
+    }
+
+    async fn delete_list(&mut self, name: &str) -> Result<Option<usize>, AppError> {
+        let deleted = self.todos.delete_list(name).await?;
+        self.push().await?;
+        Ok(deleted)
+    }
+
+    async fn move_todo(&mut self, id: u32, list: &str) -> Result<Option<&Todo>, AppError> {
+        let moved = self.todos.move_todo(id, list).await?.is_some();
+        self.push().await?;
+        Ok(self.todos.get(id).filter(|_| moved))
+    }
+
+    async fn archive_done(&mut self, min_age_days: u32) -> Result<usize, AppError> {
+        let count = self.todos.archive_done(min_age_days).await?;
+        self.push().await?;
+        Ok(count)
+    }
+
+    async fn archived(&self, search: &str) -> Result<Vec<&Todo>, AppError> {
+        self.todos.archived(search).await
+    }
+
+    async fn restore(&mut self, key: &str) -> Result<Option<&Todo>, AppError> {
+        let id = self.todos.restore(key).await?.map(|todo| todo.id);
+        self.push().await?;
+        Ok(id.and_then(|id| self.todos.get(id)))
+    }
+
+    async fn bulk(&mut self, ids: &[u32], operation: &Bulk) -> Result<usize, AppError> {
+        let count = self.todos.bulk(ids, operation).await?;
+        self.push().await?;
+        Ok(count)
+    }
+
+    async fn trash(&self) -> Result<Vec<&Todo>, AppError> {
+        self.todos.trash().await
+    }
+
+    async fn untrash(&mut self, key: &str) -> Result<Option<&Todo>, AppError> {
+        let id = self.todos.untrash(key).await?.map(|todo| todo.id);
+        self.push().await?;
+        Ok(id.and_then(|id| self.todos.get(id)))
+    }
+
+    async fn empty_trash(&mut self) -> Result<usize, AppError> {
+        self.todos.empty_trash().await
+    }
+
+    async fn purge_trash(&mut self, retention_days: u32) -> Result<usize, AppError> {
+        self.todos.purge_trash(retention_days).await
+    }
+
+    /// A ordem manual é local, não vai para o servidor.
+    async fn reorder(&mut self, id: u32, position: Position) -> Result<Option<usize>, AppError> {
+        self.todos.reorder(id, position).await
+    }
+
+    async fn set_passphrase(&mut self, passphrase: Option<String>) -> Result<(), AppError> {
+        self.todos.set_passphrase(passphrase).await
+    }
+
+    async fn encrypted(&self) -> Result<bool, AppError> {
+        self.todos.encrypted().await
+    }
+
+    /// O tempo registrado não vai para o servidor: o VTODO não tem onde guardá-lo.
+    async fn start_timer(&mut self, id: u32) -> Result<Option<&Todo>, AppError> {
+        self.todos.start_timer(id).await
+    }
+
+    async fn stop_timer(&mut self) -> Result<Option<&Todo>, AppError> {
+        self.todos.stop_timer().await
+    }
+
+    async fn running_timer(&self) -> Result<Option<&Todo>, AppError> {
+        self.todos.running_timer().await
+    }
+
+    async fn time_report(&self, from: NaiveDate, to: NaiveDate) -> Result<Report, AppError> {
+        self.todos.time_report(from, to).await
+    }
+
+    async fn complete_pomodoro(&mut self, id: u32) -> Result<Option<&Todo>, AppError> {
+        self.todos.complete_pomodoro(id).await
+    }
+}
+
+async fn expect_success(response: reqwest::Response) -> Result<reqwest::Response, Failure> {
+    let status = response.status();
+    if status.is_success() {
+        Ok(response)
+    } else {
+        let body = response.text().await.unwrap_or_default();
+        Err(Failure::Error(AppError::Sync(format!(
+            "o servidor respondeu {status}: {body}"
+        ))))
+    }
+}
+
+fn etag_header(response: &reqwest::Response) -> Option<String> {
+    response
+        .headers()
+        .get(header::ETAG)
+        .and_then(|etag| etag.to_str().ok())
+        .map(str::to_string)
+}
+
+/// Conteúdo de todos os elementos com o nome local `name`, ignorando o prefixo do
+/// namespace (`<d:href>`, `<D:href>` e `<href>` são o mesmo elemento).
+fn elements<'a>(xml: &'a str, name: &str) -> Vec<&'a str> {
+    let mut found = Vec::new();
+    let mut rest = xml;
+    while let Some(start) = rest.find('<') {
+        rest = &rest[start + 1..];
+        let Some(end) = rest.find('>') else {
+            break;
+        };
+        let tag = &rest[..end];
+        let qualified = tag.split_whitespace().next().unwrap_or("");
+        let local = qualified.rsplit(':').next().unwrap_or(qualified);
+        rest = &rest[end + 1..];
+        if local != name || tag.ends_with('/') || tag.starts_with(['/', '?', '!']) {
+            continue;
+        }
+        let closing = format!("</{qualified}>");
+        if let Some(close) = rest.find(&closing) {
+            found.push(&rest[..close]);
+            rest = &rest[close + closing.len()..];
+        }
+    }
+    found
+}
+
+fn unescape(text: &str) -> String {
+    let text = text.trim();
+    if let Some(cdata) = text
+        .strip_prefix("<![CDATA[")
+        .and_then(|text| text.strip_suffix("]]>"))
+    {
+        return cdata.to_string();
+    }
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&#13;", "\r")
+        .replace("&#xD;", "\r")
+        .replace("&amp;", "&")
+}
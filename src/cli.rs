@@ -1,8 +1,13 @@
 use std::fmt::Display;
 
 use crate::{
+    formats::{Format, Import},
+    pomodoro::{Phase, Pomodoro},
     terminal::{Action, UserInterface},
-    todo::TodoStorage,
+    todo::{Bulk, Position, Todo, TodoStorage, DEFAULT_LIST},
+    tracking::{self, TimeEntry},
+    validation::{ValidationError, Validator},
+    watcher::FileWatcher,
 };
 
 use tokio::io;
@@ -13,6 +18,16 @@ pub enum AppError {
     Write(io::Error),
     Read(io::Error),
     Parse(serde_json::Error),
+    Lock(io::Error),
+    Conflict,
+    Import(String),
+    Sync(String),
+    Git(String),
+    Editor(io::Error),
+    Validation(ValidationError),
+    WrongPassphrase,
+    NewerVersion(u32),
+    Corrupted(String),
 }
 impl Display for AppError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -22,6 +37,22 @@ impl Display for AppError {
             Self::Write(err) => write!(f, "Não foi possível escrever no arquivo: {err}"),
             Self::Read(err) => write!(f, "Não foi possível ler o arquivo: {err}"),
             Self::Parse(err) => write!(f, "Não foi possível parsear o arquivo: {err}"),
+            Self::Lock(err) => write!(f, "Não foi possível travar o arquivo: {err}"),
+            Self::Conflict => write!(f, "O arquivo foi alterado por outro processo"),
+            Self::Import(msg) => write!(f, "Não foi possível importar o arquivo: {msg}"),
+            Self::Sync(msg) => write!(f, "Erro na sincronização: {msg}"),
+            Self::Git(msg) => write!(f, "Erro no repositório git: {msg}"),
+            Self::Editor(err) => write!(f, "Não foi possível abrir o editor: {err}"),
+            Self::Validation(err) => write!(f, "TODO inválido: {err}"),
+            Self::WrongPassphrase => {
+                write!(f, "Senha incorreta ou ausente para o arquivo criptografado")
+            }
+            Self::NewerVersion(version) => write!(
+                f,
+                "O arquivo está no formato {version}, de uma versão mais nova do todo; este programa lê até o formato {}",
+                crate::schema::VERSION
+            ),
+            Self::Corrupted(msg) => write!(f, "O arquivo criptografado está corrompido: {msg}"),
         }
     }
 }
@@ -29,6 +60,9 @@ impl Display for AppError {
 pub struct TodoCli {
     user_interface: Box<dyn UserInterface>,
     todo_storage: Box<dyn TodoStorage>,
+    watcher: Option<FileWatcher>,
+    validator: Validator,
+    pomodoro: Pomodoro,
 }
 
 impl TodoCli {
@@ -36,10 +70,112 @@ impl TodoCli {
         Self {
             user_interface,
             todo_storage,
+            watcher: None,
+            validator: Validator::default(),
+            pomodoro: Pomodoro::default(),
+        }
+    }
+
+    pub fn watch(mut self, watcher: FileWatcher) -> Self {
+        self.watcher = Some(watcher);
+        self
+    }
+
+    pub fn validate(mut self, validator: Validator) -> Self {
+        self.validator = validator;
+        self
+    }
+
+    pub fn pomodoro(mut self, pomodoro: Pomodoro) -> Self {
+        self.pomodoro = pomodoro;
+        self
+    }
+
+    /// Valida o texto digitado. Devolve `None` quando o usuário precisa corrigir o
+    /// texto ou desistiu de gravar um TODO duplicado.
+    async fn validate_message(
+        &mut self,
+        text: &str,
+        id: Option<u32>,
+    ) -> Result<Option<String>, AppError> {
+        let open = self.todo_storage.list().await?;
+        match self.validator.message(text, id, &open) {
+            Ok(message) => {
+                if let Some(duplicate) = message.duplicate {
+                    let question =
+                        format!("Já existe um TODO aberto com esse texto ({duplicate}). Gravar mesmo assim?");
+                    if !self.user_interface.confirm(&question).await? {
+                        return Ok(None);
+                    }
+                }
+                Ok(Some(message.text))
+            }
+            Err(err @ AppError::Validation(_)) => {
+                self.user_interface.show_error(&err.to_string()).await?;
+                Ok(None)
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Aplica ao que veio do arquivo as mesmas regras do texto digitado. Textos
+    /// inválidos e TODOs novos que repetem um aberto (da lista ou do próprio arquivo)
+    /// saem da importação e viram erros da pré-visualização.
+    async fn validate_import(&mut self, import: &mut Import) -> Result<(), AppError> {
+        let open = self.todo_storage.list().await?;
+        let mut accepted: Vec<Todo> = Vec::new();
+        let mut sources = Vec::new();
+        let todos = std::mem::take(&mut import.todos);
+        for (mut todo, source) in todos.into_iter().zip(std::mem::take(&mut import.sources)) {
+            // TODOs que já existem (id diferente de 0) não contam como duplicados de si mesmos
+            let id = (todo.id != 0).then_some(todo.id);
+            let known: Vec<&Todo> = open.iter().copied().chain(&accepted).collect();
+            match self.validator.message(&todo.message, id, &known) {
+                Ok(message) => match message.duplicate {
+                    // os novos do próprio arquivo ainda não têm id
+                    Some(0) if id.is_none() => import
+                        .errors
+                        .push(format!("{source}: o texto se repete no arquivo")),
+                    Some(duplicate) if id.is_none() => import.errors.push(format!(
+                        "{source}: já existe um TODO aberto com esse texto ({duplicate})"
+                    )),
+                    _ => {
+                        todo.message = message.text;
+                        accepted.push(todo);
+                        sources.push(source);
+                    }
+                },
+                Err(AppError::Validation(err)) => import.errors.push(format!("{source}: {err}")),
+                Err(err) => return Err(err),
+            }
+        }
+        import.todos = accepted;
+        import.sources = sources;
+        Ok(())
+    }
+
+    async fn file_changed(watcher: &mut Option<FileWatcher>) -> Result<(), AppError> {
+        match watcher {
+            Some(watcher) => watcher.changed().await,
+            None => std::future::pending().await,
         }
     }
+
+    /// Recarrega a lista silenciosamente se o arquivo mudou enquanto o usuário
+    /// estava em outro fluxo.
+    async fn sync_external_changes(&mut self) -> Result<(), AppError> {
+        if let Some(watcher) = &mut self.watcher {
+            if watcher.has_changed() {
+                self.todo_storage.refresh().await?;
+            }
+        }
+        Ok(())
+    }
     async fn add(&mut self) -> Result<(), AppError> {
         let item = self.user_interface.add_todo().await?;
+        let Some(item) = self.validate_message(&item, None).await? else {
+            return self.user_interface.press_key().await;
+        };
 
         if let Some(todo) = self.todo_storage.add(item).await? {
             self.user_interface
@@ -70,7 +206,7 @@ impl TodoCli {
     async fn delete(&mut self, id: u32) -> Result<(), AppError> {
         if let Some(todo) = self.todo_storage.delete(id).await? {
             self.user_interface
-                .show_sucess(&todo, "deletado com sucesso")
+                .show_sucess(&todo, "movido para a lixeira")
                 .await?;
         } else {
             self.user_interface
@@ -82,6 +218,9 @@ impl TodoCli {
     }
 
     async fn update(&mut self, id: u32, message: String) -> Result<(), AppError> {
+        let Some(message) = self.validate_message(&message, Some(id)).await? else {
+            return Ok(());
+        };
         if let Some(todo) = self.todo_storage.update(id, message).await? {
             self.user_interface
                 .show_sucess(todo, "atualizado com sucesso!")
@@ -94,23 +233,419 @@ impl TodoCli {
         Ok(())
     }
 
+    async fn notes(&mut self, id: u32, notes: &str) -> Result<(), AppError> {
+        let notes = match self.user_interface.edit_notes(notes).await {
+            Ok(notes) => notes,
+            Err(err @ AppError::Editor(_)) => {
+                return self.user_interface.show_error(&err.to_string()).await
+            }
+            Err(err) => return Err(err),
+        };
+        if let Some(todo) = self.todo_storage.update_notes(id, notes).await? {
+            self.user_interface
+                .show_sucess(todo, "com as anotações atualizadas")
+                .await?;
+        } else {
+            self.user_interface
+                .show_error("Não foi possível atualizar as anotações")
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// `todo reorder <id> <posição>`: muda a posição do TODO na lista em que ele está,
+    /// que passa a ser a atual.
+    pub async fn reorder_command(&mut self, key: &str, position: Position) -> Result<(), AppError> {
+        let Some(todo) = self.todo_storage.locate(key).await? else {
+            return self
+                .user_interface
+                .show_error("Não existe um TODO com esse ID")
+                .await;
+        };
+        let (id, list) = (todo.id, todo.list.clone());
+        self.todo_storage.switch_list(&list).await?;
+        self.reorder(id, position).await
+    }
+
+    async fn reorder(&mut self, id: u32, position: Position) -> Result<(), AppError> {
+        if let Some(position) = self.todo_storage.reorder(id, position).await? {
+            self.user_interface
+                .show_info(&format!("TODO {id} agora está na posição {position}"))
+                .await?;
+        } else {
+            self.user_interface
+                .show_error("Não foi possível mudar a posição do TODO")
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Inicia o cronômetro do TODO; o que estiver em andamento em outro é parado.
+    async fn start_timer(&mut self, id: u32) -> Result<(), AppError> {
+        let previous = self
+            .todo_storage
+            .running_timer()
+            .await?
+            .map(|todo| todo.id)
+            .filter(|previous| *previous != id);
+        if let Some(todo) = self.todo_storage.start_timer(id).await? {
+            self.user_interface
+                .show_sucess(todo, "com o cronômetro iniciado")
+                .await?;
+            if let Some(previous) = previous {
+                self.user_interface
+                    .show_info(&format!("O cronômetro do TODO {previous} foi parado"))
+                    .await?;
+            }
+        } else {
+            self.user_interface
+                .show_error("Não foi possível iniciar o cronômetro")
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn stop_timer(&mut self) -> Result<(), AppError> {
+        if let Some(todo) = self.todo_storage.stop_timer().await? {
+            let spent = todo
+                .time
+                .last()
+                .map(TimeEntry::duration)
+                .unwrap_or_default();
+            self.user_interface
+                .show_sucess(
+                    todo,
+                    &format!(
+                        "com o cronômetro parado depois de {}",
+                        tracking::format_duration(spent)
+                    ),
+                )
+                .await?;
+        } else {
+            self.user_interface
+                .show_error("Nenhum cronômetro está em andamento")
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Pomodoros seguidos no TODO até o usuário parar. O tempo de trabalho vai para o
+    /// cronômetro do TODO, a não ser que ele já estivesse em andamento; o cronômetro
+    /// de outro TODO fica parado durante os pomodoros e volta a contar no fim.
+    async fn run_pomodoro(&mut self, id: u32) -> Result<(), AppError> {
+        let paused = self
+            .todo_storage
+            .running_timer()
+            .await?
+            .map(|todo| todo.id)
+            .filter(|running| *running != id);
+        let result = self.pomodoros(id).await;
+        if let Some(paused) = paused {
+            if self.todo_storage.start_timer(paused).await?.is_some() {
+                self.user_interface
+                    .show_info(&format!("O cronômetro do TODO {paused} voltou a contar"))
+                    .await?;
+            }
+        }
+        result
+    }
+
+    async fn pomodoros(&mut self, id: u32) -> Result<(), AppError> {
+        loop {
+            let tracked = self.todo_storage.running_timer().await?.map(|todo| todo.id) != Some(id);
+            let Some(todo) = self.todo_storage.start_timer(id).await?.cloned() else {
+                return self
+                    .user_interface
+                    .show_error("Não foi possível iniciar o pomodoro")
+                    .await;
+            };
+            let finished = self
+                .user_interface
+                .countdown(&todo, Phase::Work, self.pomodoro.work)
+                .await?;
+            if tracked {
+                self.todo_storage.stop_timer().await?;
+            }
+            if !finished {
+                return self
+                    .user_interface
+                    .show_info("Pomodoro interrompido, ele não foi contado")
+                    .await;
+            }
+            if let Some(todo) = self.todo_storage.complete_pomodoro(id).await? {
+                self.user_interface
+                    .show_sucess(todo, &format!("com {} pomodoros", todo.pomodoros))
+                    .await?;
+            }
+            if !self.user_interface.confirm("Fazer a pausa?").await? {
+                return Ok(());
+            }
+            let finished = self
+                .user_interface
+                .countdown(&todo, Phase::Break, self.pomodoro.pause)
+                .await?;
+            if !finished
+                || !self
+                    .user_interface
+                    .confirm("Começar outro pomodoro?")
+                    .await?
+            {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Tempo registrado por TODO, tag e dia no período escolhido.
+    async fn report(&mut self) -> Result<(), AppError> {
+        let Some((from, to)) = self.user_interface.ask_for_period().await? else {
+            self.user_interface
+                .show_error("O período informado é inválido")
+                .await?;
+            return self.user_interface.press_key().await;
+        };
+        let report = self.todo_storage.time_report(from, to).await?;
+        if report.todos.is_empty() {
+            self.user_interface
+                .show_info("Nenhum tempo registrado no período")
+                .await?;
+        } else {
+            self.user_interface.show_report(&report).await?;
+        }
+        self.user_interface.press_key().await
+    }
+
+    async fn move_todo(&mut self, id: u32, list: String) -> Result<(), AppError> {
+        if let Some(todo) = self.todo_storage.move_todo(id, &list).await? {
+            self.user_interface
+                .show_sucess(todo, &format!("movido para a lista '{list}'"))
+                .await?;
+        } else {
+            self.user_interface
+                .show_error("Não existe uma lista aberta com esse nome")
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn manage_lists(&mut self) -> Result<(), AppError> {
+        let lists = self.todo_storage.lists().await?;
+        match self.user_interface.ask_for_list_action(&lists).await? {
+            Action::SwitchList(name) => {
+                if self.todo_storage.switch_list(&name).await? {
+                    self.user_interface
+                        .show_info(&format!("Lista '{name}' selecionada"))
+                        .await?;
+                } else {
+                    self.user_interface
+                        .show_error("Não existe uma lista aberta com esse nome")
+                        .await?;
+                }
+            }
+            Action::CreateList(name) => {
+                if self.todo_storage.create_list(&name).await? {
+                    self.user_interface
+                        .show_info(&format!("Lista '{name}' criada"))
+                        .await?;
+                } else {
+                    self.user_interface
+                        .show_error("Já existe uma lista com esse nome ou o nome é inválido")
+                        .await?;
+                }
+            }
+            Action::RenameList(name, new_name) => {
+                if self.todo_storage.rename_list(&name, &new_name).await? {
+                    self.user_interface
+                        .show_info(&format!("Lista '{name}' renomeada para '{new_name}'"))
+                        .await?;
+                } else {
+                    self.user_interface
+                        .show_error("Não foi possível renomear a lista")
+                        .await?;
+                }
+            }
+            Action::ArchiveList(name, archived) => {
+                if self.todo_storage.archive_list(&name, archived).await? {
+                    let msg = if archived {
+                        "arquivada"
+                    } else {
+                        "desarquivada"
+                    };
+                    self.user_interface
+                        .show_info(&format!("Lista '{name}' {msg}"))
+                        .await?;
+                } else {
+                    self.user_interface
+                        .show_error("Não foi possível arquivar a lista")
+                        .await?;
+                }
+            }
+            Action::DeleteList(name) => {
+                let question = format!(
+                    "Deletar a lista '{name}'? Os TODOs dela vão para a lixeira da lista '{DEFAULT_LIST}'"
+                );
+                if self.user_interface.confirm(&question).await? {
+                    match self.todo_storage.delete_list(&name).await? {
+                        Some(count) => {
+                            self.user_interface
+                                .show_info(&format!(
+                                    "Lista '{name}' deletada, {count} TODOs foram para a lixeira da lista '{DEFAULT_LIST}'"
+                                ))
+                                .await?
+                        }
+                        None => {
+                            self.user_interface
+                                .show_error("Não foi possível deletar a lista")
+                                .await?
+                        }
+                    }
+                }
+            }
+            _ => return Ok(()),
+        }
+        self.user_interface.press_key().await?;
+        Ok(())
+    }
+
+    async fn archive(&mut self) -> Result<(), AppError> {
+        let mut search = String::new();
+        loop {
+            let list = self.todo_storage.archived(&search).await?;
+            match self
+                .user_interface
+                .ask_for_archive_action(list, &search)
+                .await?
+            {
+                Action::ArchiveDone => {
+                    let count = self.todo_storage.archive_done(0).await?;
+                    self.user_interface
+                        .show_info(&format!("{count} TODOs arquivados"))
+                        .await?;
+                }
+                Action::SearchArchive(text) => {
+                    search = text;
+                    continue;
+                }
+                Action::Restore(id) => {
+                    if let Some(todo) = self.todo_storage.restore(&id).await? {
+                        self.user_interface
+                            .show_sucess(todo, "restaurado com sucesso")
+                            .await?;
+                    } else {
+                        self.user_interface
+                            .show_error("Não existe um TODO arquivado com esse ID")
+                            .await?;
+                    }
+                }
+                _ => return Ok(()),
+            }
+            self.user_interface.press_key().await?;
+        }
+    }
+
+    /// Criptografa o arquivo com uma nova senha; uma senha vazia remove a criptografia.
+    async fn passphrase(&mut self) -> Result<(), AppError> {
+        let passphrase = self
+            .user_interface
+            .ask_passphrase("Digite a nova senha (vazia para remover a criptografia):")
+            .await?;
+        if passphrase.is_empty() {
+            if self
+                .user_interface
+                .confirm("Gravar o arquivo sem criptografia?")
+                .await?
+            {
+                self.todo_storage.set_passphrase(None).await?;
+                self.user_interface
+                    .show_info("O arquivo agora é gravado sem criptografia")
+                    .await?;
+            }
+            return self.user_interface.press_key().await;
+        }
+        let repeated = self
+            .user_interface
+            .ask_passphrase("Digite a senha de novo:")
+            .await?;
+        if repeated != passphrase {
+            self.user_interface
+                .show_error("As senhas não conferem")
+                .await?;
+        } else {
+            self.todo_storage.set_passphrase(Some(passphrase)).await?;
+            self.user_interface
+                .show_info("Senha alterada, o arquivo está criptografado")
+                .await?;
+        }
+        self.user_interface.press_key().await
+    }
+
+    async fn trash(&mut self) -> Result<(), AppError> {
+        loop {
+            let list = self.todo_storage.trash().await?;
+            match self.user_interface.ask_for_trash_action(list).await? {
+                Action::Restore(id) => {
+                    if let Some(todo) = self.todo_storage.untrash(&id).await? {
+                        self.user_interface
+                            .show_sucess(todo, "restaurado da lixeira")
+                            .await?;
+                    } else {
+                        self.user_interface
+                            .show_error("Não existe um TODO na lixeira com esse ID")
+                            .await?;
+                    }
+                }
+                Action::EmptyTrash => {
+                    if !self
+                        .user_interface
+                        .confirm("Apagar de vez os TODOs da lixeira?")
+                        .await?
+                    {
+                        continue;
+                    }
+                    let count = self.todo_storage.empty_trash().await?;
+                    self.user_interface
+                        .show_info(&format!("{count} TODOs apagados de vez"))
+                        .await?;
+                }
+                _ => return Ok(()),
+            }
+            self.user_interface.press_key().await?;
+        }
+    }
+
     async fn edit(&mut self) -> Result<(), AppError> {
+        self.sync_external_changes().await?;
         self.user_interface
             .list_todo(self.todo_storage.list().await?)
             .await?;
-        if let Some(id) = self.user_interface.select_todo().await? {
-            if !self.todo_storage.exist(id).await? {
-                self.user_interface
-                    .show_error("Não existe um TODO com esse ID")
-                    .await?;
-            } else {
-                let action = self.user_interface.ask_for_todo_action(id).await?;
+        if let Some(key) = self.user_interface.select_todo().await? {
+            let todo = self.todo_storage.find(&key).await?.cloned();
+            if let Some(todo) = todo {
+                self.user_interface.show_todo(&todo).await?;
+                let action = self.user_interface.ask_for_todo_action(&todo).await?;
                 match action {
                     Action::Done(id) => self.done(id).await?,
                     Action::Delete(id) => self.delete(id).await?,
+                    // texto apagado ou sem alteração cancela a edição
+                    Action::Update(_, message)
+                        if message.trim().is_empty() || message == todo.message =>
+                    {
+                        self.user_interface
+                            .show_info("Texto não alterado, o TODO foi mantido")
+                            .await?
+                    }
                     Action::Update(id, message) => self.update(id, message).await?,
+                    Action::Move(id, list) => self.move_todo(id, list).await?,
+                    Action::Notes(id) => self.notes(id, &todo.notes).await?,
+                    Action::Reorder(id, position) => self.reorder(id, position).await?,
+                    Action::StartTimer(id) => self.start_timer(id).await?,
+                    Action::StopTimer => self.stop_timer().await?,
+                    Action::Pomodoro(id) => self.run_pomodoro(id).await?,
                     _ => (),
                 };
+            } else {
+                self.user_interface
+                    .show_error("Não existe um TODO com esse ID")
+                    .await?;
             }
         } else {
             self.user_interface
@@ -123,24 +658,260 @@ impl TodoCli {
         Ok(())
     }
 
+    /// Aplica uma operação a vários TODOs de uma vez, com uma única gravação.
+    async fn bulk(&mut self) -> Result<(), AppError> {
+        self.sync_external_changes().await?;
+        self.user_interface
+            .list_todo(self.todo_storage.list().await?)
+            .await?;
+        let Some(selection) = self.user_interface.select_todos().await? else {
+            self.user_interface
+                .show_error("A seleção informada é inválida")
+                .await?;
+            return self.user_interface.press_key().await;
+        };
+        let selected: Vec<&Todo> = self
+            .todo_storage
+            .list()
+            .await?
+            .into_iter()
+            .filter(|todo| selection.matches(todo))
+            .collect();
+        let ids: Vec<u32> = selected.iter().map(|todo| todo.id).collect();
+        let messages: Vec<(u32, String)> = selected
+            .iter()
+            .map(|todo| (todo.id, todo.message.clone()))
+            .collect();
+        if ids.is_empty() {
+            self.user_interface
+                .show_error("Nenhum TODO corresponde à seleção")
+                .await?;
+            return self.user_interface.press_key().await;
+        }
+        self.user_interface.list_todo(selected).await?;
+
+        let Some(mut operation) = self.user_interface.ask_for_bulk_action(ids.len()).await? else {
+            return Ok(());
+        };
+        if let Bulk::Tag(tag) = &mut operation {
+            if let Err(error) = self.validate_tag(tag, &messages) {
+                self.user_interface.show_error(&error).await?;
+                return self.user_interface.press_key().await;
+            }
+        }
+        if let Bulk::Delete = operation {
+            let question = format!("Mover {} TODOs para a lixeira?", ids.len());
+            if !self.user_interface.confirm(&question).await? {
+                return Ok(());
+            }
+        }
+        let count = self.todo_storage.bulk(&ids, &operation).await?;
+        if count == 0 {
+            let message = match operation {
+                Bulk::Move(_) => "Não existe uma lista aberta com esse nome",
+                _ => "Nenhum TODO foi alterado",
+            };
+            self.user_interface.show_error(message).await?;
+        } else {
+            self.user_interface
+                .show_info(&format!("{count} TODOs alterados"))
+                .await?;
+        }
+        self.user_interface.press_key().await
+    }
+
+    /// Passa a tag e o texto que cada TODO vai ter com ela pelas mesmas regras do
+    /// texto digitado, deixando a tag já normalizada.
+    fn validate_tag(&self, tag: &mut String, messages: &[(u32, String)]) -> Result<(), String> {
+        let text = match self.validator.message(tag, None, &[]) {
+            Ok(message) => message.text,
+            Err(AppError::Validation(err)) => return Err(format!("Tag inválida: {err}")),
+            Err(err) => return Err(err.to_string()),
+        };
+        if text.contains(' ') {
+            return Err("Tag inválida: a tag não pode ter espaços".to_string());
+        }
+        *tag = text;
+        for (id, message) in messages {
+            if message.split_whitespace().any(|word| word == tag) {
+                continue;
+            }
+            match self
+                .validator
+                .message(&format!("{message} {tag}"), Some(*id), &[])
+            {
+                Ok(_) => {}
+                Err(AppError::Validation(err)) => return Err(format!("TODO {id}: {err}")),
+                Err(err) => return Err(err.to_string()),
+            }
+        }
+        Ok(())
+    }
+
     async fn list(&mut self) -> Result<(), AppError> {
         self.user_interface
             .list_todo(self.todo_storage.list().await?)
             .await?;
+        loop {
+            tokio::select! {
+                result = self.user_interface.press_key() => return result,
+                result = Self::file_changed(&mut self.watcher) => {
+                    result?;
+                    if self.todo_storage.refresh().await? {
+                        self.user_interface
+                            .refresh_todo(self.todo_storage.list().await?)
+                            .await?;
+                    }
+                }
+            }
+        }
+    }
+
+    async fn ask_for_file(&mut self) -> Result<Option<(Format, String)>, AppError> {
+        let Some(format) = self.user_interface.select_format().await? else {
+            self.user_interface
+                .show_error("O formato informado é inválido")
+                .await?;
+            return Ok(None);
+        };
+        let path = self.user_interface.ask_for_path().await?;
+        Ok(Some((format, path)))
+    }
+
+    async fn import(&mut self) -> Result<(), AppError> {
+        if let Some((format, path)) = self.ask_for_file().await? {
+            let import = match tokio::fs::read_to_string(&path).await {
+                Ok(contents) => format.import(&contents, &self.todo_storage.list().await?),
+                Err(err) => Err(AppError::Read(err)),
+            };
+            match import {
+                Ok(mut import) => {
+                    self.validate_import(&mut import).await?;
+                    // pré-visualização: nada é gravado antes da confirmação
+                    self.user_interface.preview_import(&import).await?;
+                    if import.todos.is_empty() {
+                        self.user_interface
+                            .show_error("Nenhum TODO para importar")
+                            .await?;
+                    } else if self
+                        .user_interface
+                        .confirm(&format!("Importar {} TODOs?", import.todos.len()))
+                        .await?
+                    {
+                        let count = self.todo_storage.import(import.todos).await?;
+                        self.user_interface
+                            .show_info(&format!("{count} TODOs importados de {path}"))
+                            .await?;
+                    }
+                }
+                Err(err @ (AppError::Read(_) | AppError::Import(_))) => {
+                    self.user_interface.show_error(&err.to_string()).await?
+                }
+                Err(err) => return Err(err),
+            }
+        }
         self.user_interface.press_key().await?;
         Ok(())
     }
 
+    async fn export(&mut self) -> Result<(), AppError> {
+        if let Some((format, path)) = self.ask_for_file().await? {
+            let todos = self.todo_storage.list().await?;
+            let count = todos.len();
+            let contents = format.export(&todos)?;
+            match tokio::fs::write(&path, contents).await {
+                Ok(()) => {
+                    self.user_interface
+                        .show_info(&format!("{count} TODOs exportados para {path}"))
+                        .await?
+                }
+                Err(err) => {
+                    self.user_interface
+                        .show_error(&AppError::Write(err).to_string())
+                        .await?
+                }
+            }
+        }
+        self.user_interface.press_key().await?;
+        Ok(())
+    }
+
+    async fn resolve_conflict(&mut self) -> Result<(), AppError> {
+        self.user_interface
+            .show_error(&AppError::Conflict.to_string())
+            .await?;
+        match self.user_interface.ask_for_conflict().await? {
+            Action::Merge => {
+                let conflicts = self.todo_storage.merge().await?;
+                if conflicts.is_empty() {
+                    self.user_interface
+                        .show_info("Alterações mescladas com sucesso")
+                        .await?;
+                } else {
+                    let ids: Vec<String> = conflicts.iter().map(u32::to_string).collect();
+                    self.user_interface
+                        .show_error(&format!(
+                            "Os TODOs {} foram alterados nos dois lados, a sua versão foi mantida",
+                            ids.join(", ")
+                        ))
+                        .await?;
+                }
+            }
+            _ => {
+                self.todo_storage.reload().await?;
+                self.user_interface
+                    .show_info("Lista recarregada do arquivo, a sua alteração foi descartada")
+                    .await?;
+            }
+        }
+        self.user_interface.press_key().await?;
+        Ok(())
+    }
+
+    /// Trata os erros que o usuário consegue resolver; os demais encerram o sistema.
+    async fn recover(&mut self, err: AppError) -> Result<(), AppError> {
+        match err {
+            AppError::Conflict => self.resolve_conflict().await,
+            err => Err(err),
+        }
+    }
+
     pub async fn run(&mut self) -> Result<(), AppError> {
         self.user_interface.welcome().await?;
         loop {
-            let action = self.user_interface.ask_for_action().await?;
-            match action {
-                Action::Add => self.add().await?,
-                Action::List => self.list().await?,
-                Action::Edit => self.edit().await?,
+            if let Err(err) = self.sync_external_changes().await {
+                self.recover(err).await?;
+            }
+            // o histórico do prompt guardaria em texto puro o que foi digitado
+            let encrypted = self.todo_storage.encrypted().await?;
+            self.user_interface.keep_history(!encrypted).await?;
+            self.user_interface
+                .complete_with(self.todo_storage.list().await?)
+                .await?;
+            let list = self.todo_storage.current_list().await?;
+            let timer = self.todo_storage.running_timer().await?;
+            let action = self.user_interface.ask_for_action(&list, timer).await?;
+            let result = match action {
+                Action::Add => self.add().await,
+                Action::List => self.list().await,
+                Action::Edit => self.edit().await,
+                Action::Bulk => self.bulk().await,
+                Action::Lists => self.manage_lists().await,
+                Action::Archive => self.archive().await,
+                Action::Trash => self.trash().await,
+                Action::StopTimer => match self.stop_timer().await {
+                    Ok(()) => self.user_interface.press_key().await,
+                    err => err,
+                },
+                Action::Report => self.report().await,
+                Action::Passphrase => self.passphrase().await,
+                Action::Import => self.import().await,
+                Action::Export => self.export().await,
                 Action::Exit => return self.user_interface.exit().await,
-                _ => (),
+                _ => Ok(()),
+            };
+            if let Err(err) = result {
+                self.recover(err).await?;
             }
         }
     }
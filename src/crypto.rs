@@ -0,0 +1,184 @@
+//! Criptografia do arquivo de TODOs com senha.
+//!
+//! O arquivo criptografado continua sendo um JSON, mas com um envelope no lugar da
+//! lista: o conteúdo é cifrado com ChaCha20-Poly1305 (autenticado, qualquer byte
+//! alterado é detectado) e a chave vem da senha pelo Argon2id, com os parâmetros e o
+//! sal gravados no próprio envelope. Junto da chave o Argon2id gera um verificador,
+//! também gravado, que separa uma senha errada de um arquivo corrompido.
+//!
+//! Os arquivos de estado do CalDAV (`.caldav`) e do sync (`.sync`) são cifrados com
+//! a mesma senha. O histórico do prompt não é salvo enquanto o arquivo estiver
+//! criptografado e os commits do armazenamento git levam só os ids dos TODOs.
+
+use crate::cli::AppError;
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chacha20poly1305::{
+    aead::{rand_core::RngCore, Aead, AeadCore, KeyInit, OsRng},
+    ChaCha20Poly1305, Key, Nonce,
+};
+use serde::{Deserialize, Serialize};
+use std::{env, io};
+
+/// Versão do envelope, gravada no campo `encrypted`.
+const VERSION: u32 = 1;
+const SALT_LEN: usize = 16;
+const KEY_LEN: usize = 32;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+struct Kdf {
+    memory: u32,
+    iterations: u32,
+    parallelism: u32,
+}
+
+impl Default for Kdf {
+    fn default() -> Self {
+        Self {
+            memory: Params::DEFAULT_M_COST,
+            iterations: Params::DEFAULT_T_COST,
+            parallelism: Params::DEFAULT_P_COST,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Envelope {
+    encrypted: u32,
+    kdf: Kdf,
+    salt: String,
+    check: String,
+    nonce: String,
+    data: String,
+}
+
+/// Chave derivada da senha, pronta para ler e gravar o arquivo.
+#[derive(Clone)]
+pub struct Cipher {
+    key: [u8; KEY_LEN],
+    check: [u8; KEY_LEN],
+    salt: Vec<u8>,
+    kdf: Kdf,
+}
+
+impl Cipher {
+    /// Deriva uma chave nova, com sal aleatório, para criptografar o arquivo.
+    pub async fn new(passphrase: &str) -> Result<Self, AppError> {
+        let mut salt = vec![0; SALT_LEN];
+        OsRng.fill_bytes(&mut salt);
+        Self::derive(passphrase, salt, Kdf::default()).await
+    }
+
+    /// Deriva a chave de um arquivo já criptografado, conferindo a senha.
+    pub async fn unlock(passphrase: &str, contents: &str) -> Result<Self, AppError> {
+        let envelope = envelope(contents)?;
+        let cipher = Self::derive(passphrase, decode(&envelope.salt)?, envelope.kdf).await?;
+        if decode(&envelope.check)? != cipher.check {
+            return Err(AppError::WrongPassphrase);
+        }
+        Ok(cipher)
+    }
+
+    /// O Argon2id é lento de propósito, então roda fora do runtime.
+    async fn derive(passphrase: &str, salt: Vec<u8>, kdf: Kdf) -> Result<Self, AppError> {
+        let passphrase = passphrase.to_string();
+        tokio::task::spawn_blocking(move || {
+            let params = Params::new(
+                kdf.memory,
+                kdf.iterations,
+                kdf.parallelism,
+                Some(KEY_LEN * 2),
+            )
+            .map_err(|err| AppError::Corrupted(err.to_string()))?;
+            let mut output = [0; KEY_LEN * 2];
+            Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+                .hash_password_into(passphrase.as_bytes(), &salt, &mut output)
+                .map_err(|err| AppError::Corrupted(err.to_string()))?;
+            let (key, check) = output.split_at(KEY_LEN);
+            Ok(Self {
+                key: key.try_into().expect("metade da saída do Argon2id"),
+                check: check.try_into().expect("metade da saída do Argon2id"),
+                salt,
+                kdf,
+            })
+        })
+        .await
+        .map_err(|err| AppError::Read(io::Error::other(err)))?
+    }
+
+    pub fn encrypt(&self, contents: &str) -> Result<String, AppError> {
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let data = ChaCha20Poly1305::new(Key::from_slice(&self.key))
+            .encrypt(&nonce, contents.as_bytes())
+            .map_err(|_| AppError::Write(io::Error::other("falha ao criptografar")))?;
+        let envelope = Envelope {
+            encrypted: VERSION,
+            kdf: self.kdf,
+            salt: STANDARD.encode(&self.salt),
+            check: STANDARD.encode(self.check),
+            nonce: STANDARD.encode(nonce),
+            data: STANDARD.encode(data),
+        };
+        serde_json::to_string(&envelope).map_err(AppError::Parse)
+    }
+
+    pub fn decrypt(&self, contents: &str) -> Result<String, AppError> {
+        let envelope = envelope(contents)?;
+        // outra senha, ex.: trocada por outro processo depois que o arquivo foi aberto
+        if decode(&envelope.salt)? != self.salt || decode(&envelope.check)? != self.check {
+            return Err(AppError::WrongPassphrase);
+        }
+        let nonce = decode(&envelope.nonce)?;
+        if nonce.len() != 12 {
+            return Err(AppError::Corrupted(
+                "nonce com tamanho inválido".to_string(),
+            ));
+        }
+        let data = ChaCha20Poly1305::new(Key::from_slice(&self.key))
+            .decrypt(
+                Nonce::from_slice(&nonce),
+                decode(&envelope.data)?.as_slice(),
+            )
+            .map_err(|_| AppError::Corrupted("o conteúdo foi alterado".to_string()))?;
+        String::from_utf8(data).map_err(|err| AppError::Corrupted(err.to_string()))
+    }
+}
+
+/// Se o conteúdo é um arquivo criptografado (e precisa de senha para ser lido).
+pub fn is_encrypted(contents: &str) -> bool {
+    serde_json::from_str::<serde_json::Value>(contents)
+        .is_ok_and(|value| value.get("encrypted").is_some())
+}
+
+/// Senha de `TODO_PASSPHRASE` ou, se não houver, a primeira linha do arquivo em
+/// `TODO_KEYFILE`. Sem nenhuma das duas a senha é perguntada ao usuário.
+pub fn passphrase_from_env() -> Result<Option<String>, AppError> {
+    if let Ok(passphrase) = env::var("TODO_PASSPHRASE") {
+        return Ok(Some(passphrase));
+    }
+    match env::var("TODO_KEYFILE") {
+        Ok(path) => {
+            let contents = std::fs::read_to_string(path).map_err(AppError::Read)?;
+            Ok(Some(contents.lines().next().unwrap_or("").to_string()))
+        }
+        Err(_) => Ok(None),
+    }
+}
+
+fn envelope(contents: &str) -> Result<Envelope, AppError> {
+    let envelope: Envelope =
+        serde_json::from_str(contents).map_err(|err| AppError::Corrupted(err.to_string()))?;
+    if envelope.encrypted != VERSION {
+        return Err(AppError::Corrupted(format!(
+            "versão {} desconhecida",
+            envelope.encrypted
+        )));
+    }
+    Ok(envelope)
+}
+
+fn decode(value: &str) -> Result<Vec<u8>, AppError> {
+    STANDARD
+        .decode(value)
+        .map_err(|err| AppError::Corrupted(err.to_string()))
+}
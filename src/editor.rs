@@ -0,0 +1,54 @@
+//! Edição de textos longos no editor do usuário (`$VISUAL` ou `$EDITOR`).
+
+use crate::cli::AppError;
+use std::{
+    env, fs,
+    io::{self, Write},
+    process,
+};
+
+/// Editor usado quando nem `$VISUAL` nem `$EDITOR` estão definidas.
+const DEFAULT_EDITOR: &str = "vi";
+
+/// Abre `text` num arquivo temporário no editor e devolve o conteúdo salvo.
+pub async fn edit(text: &str) -> Result<String, AppError> {
+    let text = text.to_string();
+    tokio::task::spawn_blocking(move || open(&text))
+        .await
+        .map_err(|err| AppError::Editor(io::Error::other(err)))?
+}
+
+fn open(text: &str) -> Result<String, AppError> {
+    let editor = env::var("VISUAL")
+        .or_else(|_| env::var("EDITOR"))
+        .unwrap_or_else(|_| DEFAULT_EDITOR.to_string());
+    // o editor pode vir com argumentos, ex.: `code --wait`
+    let mut words = editor.split_whitespace();
+    let program = words.next().unwrap_or(DEFAULT_EDITOR);
+
+    // o texto fica num diretório só do usuário, num arquivo criado com `O_EXCL` e
+    // permissão 0600; os dois somem ao final, mesmo se o editor falhar
+    let dir = tempfile::Builder::new()
+        .prefix("todo-")
+        .tempdir()
+        .map_err(AppError::Editor)?;
+    let mut file = tempfile::Builder::new()
+        .suffix(".md")
+        .tempfile_in(&dir)
+        .map_err(AppError::Editor)?;
+    file.write_all(text.as_bytes()).map_err(AppError::Editor)?;
+    // fecha o arquivo antes de abrir o editor, que pode querer substituí-lo
+    let path = file.into_temp_path();
+    let result = match process::Command::new(program)
+        .args(words)
+        .arg(&path)
+        .status()
+    {
+        Ok(status) if status.success() => fs::read_to_string(&path).map_err(AppError::Editor),
+        Ok(status) => Err(AppError::Editor(io::Error::other(format!(
+            "o editor terminou com {status}"
+        )))),
+        Err(err) => Err(AppError::Editor(err)),
+    };
+    result
+}
@@ -0,0 +1,225 @@
+//! Exportação e importação em CSV com linha de cabeçalho.
+//!
+//! As colunas da importação são mapeadas pelo nome do cabeçalho (em português ou
+//! inglês, sem diferenciar maiúsculas). Colunas desconhecidas são ignoradas e
+//! cada linha inválida vira um erro com o seu número, sem impedir as demais.
+
+use super::Import;
+use crate::{
+    cli::AppError,
+    todo::{parse_date, today, Todo},
+};
+use chrono::NaiveDate;
+use std::io;
+
+const HEADER: [&str; 9] = [
+    "id",
+    "uuid",
+    "message",
+    "done",
+    "priority",
+    "created",
+    "completed",
+    "metadata",
+    "notes",
+];
+
+#[derive(Clone, Copy, PartialEq)]
+enum Column {
+    Id,
+    Uuid,
+    Message,
+    Done,
+    Priority,
+    Created,
+    Completed,
+    Metadata,
+    Notes,
+}
+
+impl Column {
+    fn from_header(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "id" => Some(Self::Id),
+            "uuid" => Some(Self::Uuid),
+            "message" | "mensagem" | "texto" | "todo" | "description" | "descrição" => {
+                Some(Self::Message)
+            }
+            "done" | "feito" | "status" => Some(Self::Done),
+            "priority" | "prioridade" => Some(Self::Priority),
+            "created" | "criado" | "criação" => Some(Self::Created),
+            "completed" | "concluído" | "conclusão" => Some(Self::Completed),
+            "metadata" | "metadados" => Some(Self::Metadata),
+            "notes" | "notas" | "anotações" | "observações" => Some(Self::Notes),
+            _ => None,
+        }
+    }
+}
+
+pub fn export(todos: &[&Todo], delimiter: u8) -> Result<String, AppError> {
+    let mut writer = csv::WriterBuilder::new()
+        .delimiter(delimiter)
+        .from_writer(Vec::new());
+    writer.write_record(HEADER).map_err(write_error)?;
+    for todo in todos {
+        let metadata: Vec<String> = todo
+            .metadata
+            .iter()
+            .map(|(key, value)| format!("{key}:{value}"))
+            .collect();
+        writer
+            .write_record([
+                todo.id.to_string(),
+                todo.uuid.clone(),
+                todo.message.clone(),
+                todo.done.to_string(),
+                todo.priority.map(String::from).unwrap_or_default(),
+                todo.created
+                    .map(|date| date.to_string())
+                    .unwrap_or_default(),
+                todo.completed
+                    .map(|date| date.to_string())
+                    .unwrap_or_default(),
+                metadata.join(" "),
+                todo.notes.clone(),
+            ])
+            .map_err(write_error)?;
+    }
+    let contents = writer
+        .into_inner()
+        .map_err(|err| AppError::Write(io::Error::other(err.to_string())))?;
+    String::from_utf8(contents).map_err(|err| AppError::Write(io::Error::other(err)))
+}
+
+pub fn import(contents: &str, delimiter: u8, existing: &[&Todo]) -> Result<Import, AppError> {
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .flexible(true)
+        .from_reader(contents.as_bytes());
+    let columns: Vec<Option<Column>> = reader
+        .headers()
+        .map_err(|err| AppError::Import(err.to_string()))?
+        .iter()
+        .map(Column::from_header)
+        .collect();
+    if !columns.contains(&Some(Column::Message)) {
+        return Err(AppError::Import(
+            "o cabeçalho não tem uma coluna 'message'".to_string(),
+        ));
+    }
+
+    let mut import = Import::default();
+    for (index, record) in reader.records().enumerate() {
+        let line = index + 2; //a linha 1 é o cabeçalho
+        let result = record
+            .map_err(|err| err.to_string())
+            .and_then(|record| row(&columns, &record, existing));
+        match result {
+            Ok(todo) => import.push(format!("Linha {line}"), todo),
+            Err(err) => import.errors.push(format!("Linha {line}: {err}")),
+        }
+    }
+    Ok(import)
+}
+
+fn row(
+    columns: &[Option<Column>],
+    record: &csv::StringRecord,
+    existing: &[&Todo],
+) -> Result<Todo, String> {
+    let mapped = |column: Column| columns.contains(&Some(column));
+    let value = |column: Column| {
+        columns
+            .iter()
+            .position(|mapped| *mapped == Some(column))
+            .and_then(|index| record.get(index))
+            .map(str::trim)
+            .unwrap_or("")
+    };
+
+    let id = match value(Column::Id) {
+        "" => 0,
+        id => id
+            .parse::<u32>()
+            .map_err(|_| format!("id inválido '{id}'"))?,
+    };
+    let uuid = value(Column::Uuid).to_lowercase();
+    let found = existing
+        .iter()
+        .find(|todo| !uuid.is_empty() && todo.uuid == uuid)
+        .or_else(|| existing.iter().find(|todo| todo.id == id));
+    let mut todo = match found {
+        Some(todo) => (*todo).clone(),
+        None => Todo {
+            created: Some(today()),
+            ..Default::default()
+        },
+    };
+
+    if !uuid.is_empty() {
+        todo.uuid = uuid;
+    }
+    todo.message = value(Column::Message).to_string();
+    if todo.message.is_empty() {
+        return Err("o texto do TODO está vazio".to_string());
+    }
+    if mapped(Column::Done) {
+        todo.done = done(value(Column::Done))?;
+    }
+    if mapped(Column::Priority) {
+        todo.priority = match value(Column::Priority) {
+            "" => None,
+            value => Some(priority(value)?),
+        };
+    }
+    if mapped(Column::Created) && !value(Column::Created).is_empty() {
+        todo.created = Some(date(value(Column::Created))?);
+    }
+    // sem a coluna, a data de conclusão só acompanha o status
+    todo.completed = match value(Column::Completed) {
+        completed if mapped(Column::Completed) && !completed.is_empty() => Some(date(completed)?),
+        _ if todo.done => todo.completed.or(Some(today())),
+        _ => None,
+    };
+    if mapped(Column::Metadata) {
+        todo.metadata.clear();
+        for pair in value(Column::Metadata).split_whitespace() {
+            let (key, value) = pair
+                .split_once(':')
+                .ok_or_else(|| format!("metadado inválido '{pair}', use chave:valor"))?;
+            todo.metadata.insert(key.to_string(), value.to_string());
+        }
+    }
+    if mapped(Column::Notes) {
+        todo.notes = value(Column::Notes).to_string();
+    }
+    Ok(todo)
+}
+
+fn done(value: &str) -> Result<bool, String> {
+    match value.to_lowercase().as_str() {
+        "" | "false" | "0" | "n" | "não" | "nao" | "no" | "pendente" => Ok(false),
+        "true" | "1" | "x" | "s" | "sim" | "yes" | "feito" => Ok(true),
+        _ => Err(format!("status inválido '{value}'")),
+    }
+}
+
+fn priority(value: &str) -> Result<char, String> {
+    let mut chars = value.chars();
+    match (chars.next(), chars.next()) {
+        (Some(priority), None) if priority.is_ascii_alphabetic() => {
+            Ok(priority.to_ascii_uppercase())
+        }
+        _ => Err(format!(
+            "prioridade inválida '{value}', use uma letra de A a Z"
+        )),
+    }
+}
+
+fn date(value: &str) -> Result<NaiveDate, String> {
+    parse_date(value).ok_or(format!("data inválida '{value}'"))
+}
+
+fn write_error(err: csv::Error) -> AppError {
+    AppError::Write(io::Error::other(err))
+}
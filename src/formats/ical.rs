@@ -0,0 +1,264 @@
+//! Componentes VTODO do iCalendar ([RFC 5545](https://www.rfc-editor.org/rfc/rfc5545)).
+//!
+//! O `UID` exportado é o uuid do TODO, que não muda nem se repete entre cópias do
+//! arquivo. VTODOs vindos de outros aplicativos guardam o `UID` original em
+//! `metadata["uid"]`, para que uma nova importação atualize o mesmo TODO. A data de
+//! vencimento fica em `metadata["due"]`, como no todo.txt.
+
+use super::Import;
+use crate::{
+    cli::AppError,
+    todo::{parse_date, today, Todo},
+};
+use chrono::Utc;
+use uuid::Uuid;
+
+type Properties = Vec<(String, String)>;
+
+pub fn export(todos: &[&Todo]) -> String {
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//TerraMagna & AlphaEdtech//TODO-CLI//PT".to_string(),
+    ];
+    let stamp = Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+    for todo in todos {
+        lines.extend(component(todo, &stamp));
+    }
+    lines.push("END:VCALENDAR".to_string());
+    lines.iter().map(|line| fold(line)).collect()
+}
+
+pub fn import(contents: &str, existing: &[&Todo]) -> Result<Import, AppError> {
+    let mut import = Import::default();
+    for (index, properties) in components(contents)?.iter().enumerate() {
+        match todo(properties, existing) {
+            Ok(todo) => import.push(format!("VTODO {}", index + 1), todo),
+            Err(err) => import.errors.push(format!("VTODO {}: {err}", index + 1)),
+        }
+    }
+    Ok(import)
+}
+
+/// O primeiro VTODO de `contents` aplicado sobre `todo`, sem casar pelo `UID`: no
+/// CalDAV é o recurso (href) que diz de qual TODO se trata.
+pub fn update(contents: &str, todo: &Todo) -> Result<Todo, AppError> {
+    let components = components(contents)?;
+    let properties = components
+        .first()
+        .ok_or_else(|| AppError::Import("o arquivo não tem VTODO".to_string()))?;
+    fill(properties, todo.clone()).map_err(AppError::Import)
+}
+
+/// Se os dois TODOs geram o mesmo VTODO, ou seja, se diferem só em campos que não
+/// vão para o iCalendar.
+pub fn same(todo: &Todo, other: &Todo) -> bool {
+    component(todo, "") == component(other, "")
+}
+
+/// O `UID` do VTODO de um TODO.
+pub fn uid(todo: &Todo) -> &str {
+    todo.metadata.get("uid").unwrap_or(&todo.uuid)
+}
+
+/// As propriedades de cada VTODO do arquivo.
+fn components(contents: &str) -> Result<Vec<Properties>, AppError> {
+    let lines = unfold(contents);
+    if lines.first().map(String::as_str) != Some("BEGIN:VCALENDAR") {
+        return Err(AppError::Import(
+            "o arquivo não começa com BEGIN:VCALENDAR".to_string(),
+        ));
+    }
+
+    let mut components = Vec::new();
+    let mut properties: Option<Properties> = None;
+    for line in lines {
+        match line.as_str() {
+            "BEGIN:VTODO" => properties = Some(Vec::new()),
+            "END:VTODO" => components.extend(properties.take()),
+            _ => {
+                if let (Some(properties), Some(property)) = (&mut properties, property(&line)) {
+                    properties.push(property);
+                }
+            }
+        }
+    }
+    Ok(components)
+}
+
+fn component(todo: &Todo, stamp: &str) -> Vec<String> {
+    let mut lines = vec![
+        "BEGIN:VTODO".to_string(),
+        format!("UID:{}", escape(uid(todo))),
+        format!("DTSTAMP:{stamp}"),
+        format!("SUMMARY:{}", escape(&todo.message)),
+        format!(
+            "STATUS:{}",
+            if todo.done {
+                "COMPLETED"
+            } else {
+                "NEEDS-ACTION"
+            }
+        ),
+    ];
+    if !todo.notes.is_empty() {
+        lines.push(format!("DESCRIPTION:{}", escape(&todo.notes)));
+    }
+    if let Some(created) = todo.created {
+        lines.push(format!("CREATED:{}T000000Z", created.format("%Y%m%d")));
+    }
+    if let Some(completed) = todo.completed {
+        lines.push(format!("COMPLETED:{}T000000Z", completed.format("%Y%m%d")));
+    }
+    if let Some(priority) = todo.priority {
+        // A..I viram 1..9, o restante fica com a menor prioridade
+        let level = (priority as u8).saturating_sub(b'A') + 1;
+        lines.push(format!("PRIORITY:{}", level.min(9)));
+    }
+    if let Some(due) = todo.metadata.get("due").and_then(|due| parse_date(due)) {
+        lines.push(format!("DUE;VALUE=DATE:{}", due.format("%Y%m%d")));
+    }
+    lines.push("END:VTODO".to_string());
+    lines
+}
+
+/// Casa o VTODO com um TODO existente pelo `UID`: o uuid do TODO ou o `UID` de
+/// outro aplicativo guardado nos metadados.
+fn todo(properties: &[(String, String)], existing: &[&Todo]) -> Result<Todo, String> {
+    let uid = get(properties, "UID").map(unescape);
+    let found = uid.as_ref().and_then(|uid| {
+        existing.iter().find(|todo| {
+            todo.uuid.eq_ignore_ascii_case(uid) || todo.metadata.get("uid") == Some(uid)
+        })
+    });
+    let todo = match found {
+        Some(todo) => (*todo).clone(),
+        None => Todo {
+            // um UID que já é um uuid (ex.: de outra cópia da lista) vira o uuid do TODO
+            uuid: uid
+                .as_deref()
+                .and_then(|uid| Uuid::parse_str(uid).ok())
+                .map(|uuid| uuid.to_string())
+                .unwrap_or_default(),
+            created: Some(today()),
+            ..Default::default()
+        },
+    };
+    fill(properties, todo)
+}
+
+fn fill(properties: &[(String, String)], mut todo: Todo) -> Result<Todo, String> {
+    let get = |name: &str| get(properties, name);
+    let summary = get("SUMMARY")
+        .map(unescape)
+        .filter(|summary| !summary.trim().is_empty())
+        .ok_or("sem SUMMARY")?;
+    match get("UID").map(unescape) {
+        Some(uid) if todo.uuid.eq_ignore_ascii_case(&uid) => {
+            todo.metadata.remove("uid");
+        }
+        Some(uid) => {
+            todo.metadata.insert("uid".to_string(), uid);
+        }
+        None => (),
+    }
+
+    todo.message = summary;
+    todo.notes = get("DESCRIPTION").map(unescape).unwrap_or_default();
+    todo.done = get("STATUS") == Some("COMPLETED") || get("COMPLETED").is_some();
+    if let Some(created) = get("CREATED") {
+        todo.created = Some(parse_date(created).ok_or(format!("CREATED inválido '{created}'"))?);
+    }
+    todo.completed = match get("COMPLETED") {
+        Some(completed) => {
+            Some(parse_date(completed).ok_or(format!("COMPLETED inválido '{completed}'"))?)
+        }
+        None if todo.done => todo.completed.or(Some(today())),
+        None => None,
+    };
+    todo.priority = match get("PRIORITY") {
+        Some(priority) => match priority.parse::<u8>() {
+            Ok(level @ 1..=9) => Some((b'A' + level - 1) as char),
+            Ok(_) => None,
+            Err(_) => return Err(format!("PRIORITY inválido '{priority}'")),
+        },
+        None => None,
+    };
+    match get("DUE") {
+        Some(due) => {
+            let due = parse_date(due).ok_or(format!("DUE inválido '{due}'"))?;
+            todo.metadata.insert("due".to_string(), due.to_string());
+        }
+        None => {
+            todo.metadata.remove("due");
+        }
+    }
+    Ok(todo)
+}
+
+fn get<'a>(properties: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    properties
+        .iter()
+        .find(|(key, _)| key == name)
+        .map(|(_, value)| value.as_str())
+}
+
+/// Separa uma linha `NOME;PARAM=X:valor` em (`NOME`, `valor`), descartando os parâmetros.
+fn property(line: &str) -> Option<(String, String)> {
+    let (name, value) = line.split_once(':')?;
+    let name = name.split(';').next().unwrap_or(name);
+    Some((name.to_uppercase(), value.to_string()))
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+fn unescape(text: &str) -> String {
+    let mut result = String::new();
+    let mut chars = text.chars();
+    while let Some(char) = chars.next() {
+        if char == '\\' {
+            match chars.next() {
+                Some('n') | Some('N') => result.push('\n'),
+                Some(other) => result.push(other),
+                None => result.push('\\'),
+            }
+        } else {
+            result.push(char);
+        }
+    }
+    result
+}
+
+/// Quebra linhas com mais de 75 bytes, como exige a RFC, sem partir caracteres UTF-8.
+fn fold(line: &str) -> String {
+    let mut folded = String::new();
+    let mut width = 0;
+    for char in line.chars() {
+        if width + char.len_utf8() > 75 {
+            folded.push_str("\r\n ");
+            width = 1;
+        }
+        folded.push(char);
+        width += char.len_utf8();
+    }
+    folded.push_str("\r\n");
+    folded
+}
+
+fn unfold(contents: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in contents.lines() {
+        let line = line.trim_end_matches('\r');
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(continuation), Some(last)) => last.push_str(continuation),
+            _ => lines.push(line.to_string()),
+        }
+    }
+    lines.retain(|line| !line.is_empty());
+    lines
+}
@@ -0,0 +1,179 @@
+//! Listas de tarefas do GitHub (`- [ ]` / `- [x]`).
+//!
+//! Cada item leva um marcador invisível `<!-- id:N -->` para que uma nova
+//! importação atualize os TODOs existentes em vez de duplicá-los. Itens sem
+//! marcador são casados pelo texto. As anotações vão em linhas indentadas logo
+//! abaixo do item; as que começam com um marcador de lista levam uma `\` na
+//! frente para não virarem itens. Um TODO com vários `+projeto`s aparece em mais
+//! de um grupo, mas volta uma vez só.
+
+use super::Import;
+use crate::todo::{today, Todo};
+use std::collections::{BTreeMap, HashSet};
+
+const NOTES_INDENT: &str = "  ";
+
+#[derive(Clone, Copy)]
+pub enum Group {
+    None,
+    Project,
+    Context,
+}
+
+pub fn export(todos: &[&Todo], group: Group) -> String {
+    let tokens: fn(&Todo) -> Vec<&str> = match group {
+        Group::None => return items(todos),
+        Group::Project => |todo| todo.projects(),
+        Group::Context => |todo| todo.contexts(),
+    };
+
+    let mut groups: BTreeMap<String, Vec<&Todo>> = BTreeMap::new();
+    let mut ungrouped = Vec::new();
+    for todo in todos {
+        let names = tokens(todo);
+        if names.is_empty() {
+            ungrouped.push(*todo);
+        }
+        for name in names {
+            groups.entry(name.to_string()).or_default().push(todo);
+        }
+    }
+
+    let prefix = if let Group::Project = group { '+' } else { '@' };
+    let mut sections: Vec<String> = groups
+        .iter()
+        .map(|(name, todos)| format!("## {prefix}{name}\n\n{}", items(todos)))
+        .collect();
+    if !ungrouped.is_empty() {
+        sections.push(format!("## Outros\n\n{}", items(&ungrouped)));
+    }
+    sections.join("\n")
+}
+
+pub fn import(contents: &str, existing: &[&Todo]) -> Import {
+    let mut import = Import::default();
+    let mut notes: Option<Vec<String>> = None;
+    let mut seen = HashSet::new();
+    for (index, line) in contents.lines().enumerate() {
+        if let Some((id, done, message)) = parse(line) {
+            finish(&mut import.todos, notes.take());
+            // o mesmo TODO exportado em outro grupo
+            if id.is_some_and(|id| !seen.insert(id)) {
+                continue;
+            }
+            let todo = item(existing, id, done, message);
+            import.push(format!("Linha {}", index + 1), todo);
+            notes = Some(Vec::new());
+        } else if let Some(note) = line.strip_prefix(NOTES_INDENT) {
+            if let Some(notes) = &mut notes {
+                notes.push(unescape(note));
+            }
+        } else {
+            finish(&mut import.todos, notes.take());
+        }
+    }
+    finish(&mut import.todos, notes);
+    import
+}
+
+fn item(existing: &[&Todo], id: Option<u32>, done: bool, message: String) -> Todo {
+    let found = existing
+        .iter()
+        .find(|todo| Some(todo.id) == id)
+        .or_else(|| existing.iter().find(|todo| todo.message == message));
+    let mut todo = match found {
+        Some(todo) => (*todo).clone(),
+        None => Todo {
+            created: Some(today()),
+            ..Default::default()
+        },
+    };
+    if todo.done != done {
+        todo.completed = done.then(today);
+    }
+    todo.done = done;
+    todo.message = message;
+    todo
+}
+
+/// Guarda as linhas indentadas lidas como anotações do último item. Itens sem
+/// anotações no arquivo mantêm as que já tinham.
+fn finish(todos: &mut [Todo], notes: Option<Vec<String>>) {
+    let Some(notes) = notes.filter(|notes| !notes.is_empty()) else {
+        return;
+    };
+    if let Some(todo) = todos.last_mut() {
+        todo.notes = notes.join("\n").trim_end().to_string();
+    }
+}
+
+fn items(todos: &[&Todo]) -> String {
+    todos
+        .iter()
+        .map(|todo| {
+            let notes: String = todo
+                .notes
+                .lines()
+                .map(|line| format!("{NOTES_INDENT}{}\n", escape(line)))
+                .collect();
+            format!(
+                "- [{}] {} <!-- id:{} -->\n{notes}",
+                if todo.done { 'x' } else { ' ' },
+                todo.message,
+                todo.id
+            )
+        })
+        .collect()
+}
+
+/// Põe uma `\` antes de um marcador de lista no começo da anotação (e antes de
+/// uma `\` que já estivesse lá, para a volta ser exata).
+fn escape(line: &str) -> String {
+    let text = line.trim_start();
+    let indent = &line[..line.len() - text.len()];
+    if text.starts_with(['-', '*', '+', '\\']) {
+        format!("{indent}\\{text}")
+    } else {
+        line.to_string()
+    }
+}
+
+fn unescape(line: &str) -> String {
+    let text = line.trim_start();
+    let indent = &line[..line.len() - text.len()];
+    match text.strip_prefix('\\') {
+        Some(text) => format!("{indent}{text}"),
+        None => line.to_string(),
+    }
+}
+
+/// Lê um item de lista de tarefas, devolvendo o id do marcador, o status e o texto.
+fn parse(line: &str) -> Option<(Option<u32>, bool, String)> {
+    let line = line.trim_start();
+    let rest = ["- ", "* ", "+ "]
+        .iter()
+        .find_map(|bullet| line.strip_prefix(bullet))?;
+    let (done, text) = if let Some(text) = rest.strip_prefix("[ ]") {
+        (false, text)
+    } else if let Some(text) = rest
+        .strip_prefix("[x]")
+        .or_else(|| rest.strip_prefix("[X]"))
+    {
+        (true, text)
+    } else {
+        return None;
+    };
+
+    let text = text.trim();
+    let (text, id) = match text
+        .strip_suffix("-->")
+        .and_then(|text| text.rsplit_once("<!-- id:"))
+    {
+        Some((text, id)) => (text.trim(), id.trim().parse().ok()),
+        None => (text, None),
+    };
+    if text.is_empty() {
+        return None;
+    }
+    Some((id, done, text.to_string()))
+}
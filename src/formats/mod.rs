@@ -0,0 +1,85 @@
+pub mod csv;
+pub mod ical;
+pub mod markdown;
+pub mod taskwarrior;
+pub mod todotxt;
+
+use crate::{cli::AppError, todo::Todo};
+use markdown::Group;
+
+/// Resultado de uma importação, exibido ao usuário antes de gravar qualquer coisa.
+#[derive(Default)]
+pub struct Import {
+    pub todos: Vec<Todo>,
+    /// Registros ignorados, cada um com a sua posição no arquivo e o motivo.
+    pub errors: Vec<String>,
+    /// Campos importados só em parte ou descartados por não terem lugar no modelo.
+    pub warnings: Vec<String>,
+    /// Posição de cada TODO no arquivo (`Linha 3`, `VTODO 2`...), na ordem de `todos`.
+    pub sources: Vec<String>,
+}
+
+impl Import {
+    pub fn push(&mut self, source: String, todo: Todo) {
+        self.sources.push(source);
+        self.todos.push(todo);
+    }
+}
+
+#[derive(Clone, Copy)]
+pub enum Format {
+    TodoTxt,
+    Markdown(Group),
+    /// CSV com o delimitador informado.
+    Csv(u8),
+    ICalendar,
+    Taskwarrior,
+}
+
+impl Format {
+    pub const ALL: [Format; 8] = [
+        Format::TodoTxt,
+        Format::Markdown(Group::None),
+        Format::Markdown(Group::Project),
+        Format::Markdown(Group::Context),
+        Format::Csv(b','),
+        Format::Csv(b';'),
+        Format::ICalendar,
+        Format::Taskwarrior,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::TodoTxt => "todo.txt",
+            Self::Markdown(Group::None) => "Markdown",
+            Self::Markdown(Group::Project) => "Markdown agrupado por +projeto",
+            Self::Markdown(Group::Context) => "Markdown agrupado por @contexto",
+            Self::Csv(b';') => "CSV separado por ';' (Excel em português)",
+            Self::Csv(_) => "CSV separado por ','",
+            Self::ICalendar => "iCalendar (.ics)",
+            Self::Taskwarrior => "Taskwarrior (JSON do task export)",
+        }
+    }
+
+    pub fn export(&self, todos: &[&Todo]) -> Result<String, AppError> {
+        match self {
+            Self::TodoTxt => Ok(todotxt::export(todos)),
+            Self::Markdown(group) => Ok(markdown::export(todos, *group)),
+            Self::Csv(delimiter) => csv::export(todos, *delimiter),
+            Self::ICalendar => Ok(ical::export(todos)),
+            Self::Taskwarrior => taskwarrior::export(todos),
+        }
+    }
+
+    /// Converte o conteúdo do arquivo em TODOs. TODOs com `id` de um TODO existente
+    /// atualizam esse TODO, os demais (`id` 0) são adicionados com um novo id.
+    pub fn import(&self, contents: &str, existing: &[&Todo]) -> Result<Import, AppError> {
+        match self {
+            Self::TodoTxt => Ok(todotxt::import(contents, existing)),
+            Self::Markdown(_) => Ok(markdown::import(contents, existing)),
+            Self::Csv(delimiter) => csv::import(contents, *delimiter, existing),
+            Self::ICalendar => ical::import(contents, existing),
+            Self::Taskwarrior => taskwarrior::import(contents, existing),
+        }
+    }
+}
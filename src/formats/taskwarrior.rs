@@ -0,0 +1,274 @@
+//! JSON do `task export` / `task import` do [Taskwarrior](https://taskwarrior.org).
+//!
+//! O `project` vira um token `+projeto` e as `tags` viram tokens `@tag` no texto do
+//! TODO; as `annotations` viram as anotações e o `uuid` é o mesmo do TODO. Os
+//! atributos simples que não têm lugar no modelo (ex.: `recur`, `scheduled` e UDAs)
+//! vão para `Todo::metadata`, com a lista deles em `metadata["taskwarrior"]`, e só
+//! esses voltam na exportação. O que não pode ser mapeado é listado no relatório
+//! da importação.
+
+use super::Import;
+use crate::{
+    cli::AppError,
+    todo::{parse_date, today, Todo},
+};
+use chrono::NaiveDate;
+use serde_json::{json, Map, Value};
+
+const DATE_FORMAT: &str = "%Y%m%dT%H%M%SZ";
+
+/// Metadado com as chaves, separadas por vírgula, que vieram do Taskwarrior.
+const KEYS: &str = "taskwarrior";
+
+/// Campos calculados pelo Taskwarrior, que não precisam ser guardados.
+const DERIVED: [&str; 4] = ["id", "urgency", "modified", "mask"];
+
+/// Atributos do Taskwarrior guardados em `Todo::metadata`. A importação troca só
+/// esses, os da importação anterior e os que vierem na tarefa; metadados de outros
+/// formatos continuam.
+const ATTRIBUTES: [&str; 7] = [
+    "due",
+    "scheduled",
+    "wait",
+    "until",
+    "recur",
+    "start",
+    "parent",
+];
+
+pub fn export(todos: &[&Todo]) -> Result<String, AppError> {
+    let tasks: Vec<Value> = todos.iter().map(|todo| task(todo)).collect();
+    serde_json::to_string_pretty(&tasks).map_err(AppError::Parse)
+}
+
+pub fn import(contents: &str, existing: &[&Todo]) -> Result<Import, AppError> {
+    // `task export` gera um array; versões antigas geram um objeto por linha
+    let tasks: Vec<Value> = match serde_json::from_str(contents) {
+        Ok(Value::Array(tasks)) => tasks,
+        _ => contents
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(serde_json::from_str)
+            .collect::<Result<_, _>>()
+            .map_err(|err| AppError::Import(err.to_string()))?,
+    };
+
+    let mut import = Import::default();
+    for (index, task) in tasks.iter().enumerate() {
+        let position = index + 1;
+        let Some(task) = task.as_object() else {
+            import
+                .errors
+                .push(format!("Tarefa {position}: não é um objeto JSON"));
+            continue;
+        };
+        match todo(task, existing, &mut import.warnings) {
+            Ok(todo) => import.push(format!("Tarefa {position}"), todo),
+            Err(err) => import.errors.push(format!("Tarefa {position}: {err}")),
+        }
+    }
+    Ok(import)
+}
+
+fn task(todo: &Todo) -> Value {
+    let projects = todo.projects();
+    let contexts = todo.contexts();
+    // o Taskwarrior só aceita um projeto; os demais continuam no texto
+    let mut project = projects.first().map(|project| format!("+{project}"));
+    let description: Vec<&str> = todo
+        .message
+        .split_whitespace()
+        .filter(|word| {
+            if project.as_deref() == Some(*word) {
+                project = None;
+                return false;
+            }
+            !(word.len() > 1 && word.starts_with('@'))
+        })
+        .collect();
+
+    let mut task = Map::new();
+    for key in keys(todo) {
+        if let Some(value) = todo.metadata.get(key) {
+            task.insert(key.to_string(), json!(value));
+        }
+    }
+    task.insert("uuid".to_string(), json!(todo.uuid));
+    task.insert("description".to_string(), json!(description.join(" ")));
+    task.insert(
+        "status".to_string(),
+        json!(if todo.done { "completed" } else { "pending" }),
+    );
+    task.insert(
+        "entry".to_string(),
+        json!(timestamp(todo.created.unwrap_or_else(today))),
+    );
+    if let Some(completed) = todo.completed {
+        task.insert("end".to_string(), json!(timestamp(completed)));
+    }
+    if let Some(due) = todo.metadata.get("due").and_then(|due| parse_date(due)) {
+        task.insert("due".to_string(), json!(timestamp(due)));
+    }
+    if let Some(priority) = todo.priority {
+        let priority = match priority {
+            'A' => "H",
+            'B' => "M",
+            _ => "L",
+        };
+        task.insert("priority".to_string(), json!(priority));
+    }
+    if let Some(project) = projects.first() {
+        task.insert("project".to_string(), json!(project));
+    }
+    if !contexts.is_empty() {
+        task.insert("tags".to_string(), json!(contexts));
+    }
+    if !todo.notes.is_empty() {
+        let entry = timestamp(todo.created.unwrap_or_else(today));
+        task.insert(
+            "annotations".to_string(),
+            json!([{ "entry": entry, "description": todo.notes }]),
+        );
+    }
+    Value::Object(task)
+}
+
+fn todo(
+    task: &Map<String, Value>,
+    existing: &[&Todo],
+    warnings: &mut Vec<String>,
+) -> Result<Todo, String> {
+    let description = task
+        .get("description")
+        .and_then(Value::as_str)
+        .filter(|description| !description.trim().is_empty())
+        .ok_or("sem 'description'")?;
+    let uuid = task.get("uuid").and_then(Value::as_str);
+    let status = task
+        .get("status")
+        .and_then(Value::as_str)
+        .unwrap_or("pending");
+    if status == "deleted" {
+        return Err(format!("'{description}' está apagada no Taskwarrior"));
+    }
+
+    let found = existing
+        .iter()
+        .find(|todo| uuid.is_some() && Some(todo.uuid.as_str()) == uuid);
+    let mut todo = match found {
+        Some(todo) => (*todo).clone(),
+        None => Todo::default(),
+    };
+    todo.uuid = uuid.map(str::to_lowercase).unwrap_or_default();
+    let previous: Vec<String> = keys(&todo).map(str::to_string).collect();
+    todo.metadata.retain(|key, _| {
+        !ATTRIBUTES.contains(&key.as_str())
+            && !previous.contains(key)
+            && !task.contains_key(key)
+            && key != KEYS
+    });
+
+    let mut message: Vec<String> = description.split_whitespace().map(str::to_string).collect();
+    if let Some(project) = task.get("project").and_then(Value::as_str) {
+        // antes dos outros projetos que ficaram no texto, como na exportação
+        let position = message
+            .iter()
+            .position(|word| word.len() > 1 && word.starts_with('+'))
+            .unwrap_or(message.len());
+        message.insert(position, format!("+{}", project.replace(' ', "_")));
+    }
+    if let Some(tags) = task.get("tags").and_then(Value::as_array) {
+        for tag in tags.iter().filter_map(Value::as_str) {
+            message.push(format!("@{}", tag.replace(' ', "_")));
+        }
+    }
+    todo.message = message.join(" ");
+    todo.done = status == "completed";
+    todo.created = Some(field_date(task, "entry")?.unwrap_or_else(today));
+    todo.completed = match field_date(task, "end")? {
+        Some(end) => Some(end),
+        None if todo.done => todo.completed.or(Some(today())),
+        None => None,
+    };
+    todo.priority = match task.get("priority").and_then(Value::as_str) {
+        Some("H") => Some('A'),
+        Some("M") => Some('B'),
+        Some("L") => Some('C'),
+        Some(other) => {
+            warnings.push(format!("'{description}': prioridade '{other}' ignorada"));
+            None
+        }
+        None => None,
+    };
+    if let Some(due) = field_date(task, "due")? {
+        todo.metadata.insert("due".to_string(), due.to_string());
+    }
+    let annotations: Vec<&str> = task
+        .get("annotations")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|annotation| annotation.get("description")?.as_str())
+        .collect();
+    todo.notes = annotations.join("\n");
+
+    let mapped = [
+        "description",
+        "status",
+        "project",
+        "tags",
+        "entry",
+        "end",
+        "priority",
+        "due",
+        "annotations",
+        "uuid",
+    ];
+    let mut stored = Vec::new();
+    for (key, value) in task {
+        if mapped.contains(&key.as_str()) || DERIVED.contains(&key.as_str()) {
+            continue;
+        }
+        // só valores simples, sem espaços, cabem no formato chave:valor
+        let value = match value {
+            Value::String(value) => Some(value.clone()),
+            Value::Number(value) => Some(value.to_string()),
+            _ => None,
+        };
+        match value.filter(|value| !value.is_empty() && !value.contains(char::is_whitespace)) {
+            Some(value) => {
+                todo.metadata.insert(key.clone(), value);
+                stored.push(key.as_str());
+            }
+            None => warnings.push(format!("'{description}': campo '{key}' não mapeado")),
+        }
+    }
+    if !stored.is_empty() {
+        todo.metadata.insert(KEYS.to_string(), stored.join(","));
+    }
+    Ok(todo)
+}
+
+/// As chaves de `Todo::metadata` que a importação do Taskwarrior guardou.
+fn keys(todo: &Todo) -> impl Iterator<Item = &str> {
+    todo.metadata
+        .get(KEYS)
+        .into_iter()
+        .flat_map(|keys| keys.split(','))
+}
+
+fn field_date(task: &Map<String, Value>, field: &str) -> Result<Option<NaiveDate>, String> {
+    match task.get(field).and_then(Value::as_str) {
+        Some(value) => parse_date(value)
+            .map(Some)
+            .ok_or(format!("data inválida em '{field}': '{value}'")),
+        None => Ok(None),
+    }
+}
+
+fn timestamp(date: NaiveDate) -> String {
+    date.and_hms_opt(0, 0, 0)
+        .unwrap_or_default()
+        .format(DATE_FORMAT)
+        .to_string()
+}
@@ -0,0 +1,162 @@
+//! Conversão entre a lista de TODOs e o formato [todo.txt](https://github.com/todotxt/todo.txt).
+//!
+//! Os tokens `+projeto` e `@contexto` ficam no próprio texto do TODO. Os campos
+//! `chave:valor` conhecidos (`due:`, `t:`, `rec:`, `h:` e as chaves já usadas em
+//! `Todo::metadata`) vão para os metadados; os demais continuam no texto, que
+//! volta igual na exportação. A prioridade de um TODO concluído é escrita como
+//! `pri:A`, seguindo a convenção do formato. As anotações não cabem num formato de
+//! uma linha por TODO e ficam de fora.
+//!
+//! O formato não tem id: na importação, uma linha com o mesmo texto de um TODO
+//! existente atualiza esse TODO em vez de duplicá-lo.
+
+use super::Import;
+use crate::todo::Todo;
+use chrono::NaiveDate;
+use std::collections::BTreeSet;
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// Chaves de extensões comuns do todo.txt: vencimento, data de início, recorrência
+/// e tarefa oculta.
+const KEYS: [&str; 4] = ["due", "t", "rec", "h"];
+
+pub fn export(todos: &[&Todo]) -> String {
+    todos
+        .iter()
+        .map(|todo| format!("{}\n", line(todo)))
+        .collect()
+}
+
+pub fn import(contents: &str, existing: &[&Todo]) -> Import {
+    let keys: BTreeSet<&str> = existing
+        .iter()
+        .flat_map(|todo| todo.metadata.keys())
+        .map(String::as_str)
+        .chain(KEYS)
+        .collect();
+    let mut matched = BTreeSet::new();
+    let mut import = Import::default();
+    for (index, line) in contents.lines().map(str::trim).enumerate() {
+        if line.is_empty() {
+            continue;
+        }
+        let todo = parse(line, &keys);
+        let found = existing
+            .iter()
+            .find(|found| found.message == todo.message && !matched.contains(&found.id));
+        let todo = match found {
+            Some(found) => {
+                matched.insert(found.id);
+                update(found, todo)
+            }
+            None => todo,
+        };
+        import.push(format!("Linha {}", index + 1), todo);
+    }
+    import
+}
+
+/// O TODO existente com os campos que o todo.txt carrega.
+fn update(existing: &Todo, todo: Todo) -> Todo {
+    let mut updated = existing.clone();
+    updated.done = todo.done;
+    updated.priority = todo.priority;
+    updated.metadata = todo.metadata;
+    updated.created = todo.created.or(existing.created);
+    updated.completed = todo.completed.or(existing.completed.filter(|_| todo.done));
+    updated
+}
+
+fn line(todo: &Todo) -> String {
+    let mut parts: Vec<String> = Vec::new();
+    if todo.done {
+        parts.push("x".to_string());
+        // a data de criação só pode aparecer depois da data de conclusão; sem ela, a
+        // de criação ocupa os dois lugares para não se perder
+        if let Some(completed) = todo.completed.or(todo.created) {
+            parts.push(completed.format(DATE_FORMAT).to_string());
+            if let Some(created) = todo.created {
+                parts.push(created.format(DATE_FORMAT).to_string());
+            }
+        }
+    } else {
+        if let Some(priority) = todo.priority {
+            parts.push(format!("({priority})"));
+        }
+        if let Some(created) = todo.created {
+            parts.push(created.format(DATE_FORMAT).to_string());
+        }
+    }
+    parts.push(todo.message.clone());
+    if todo.done {
+        if let Some(priority) = todo.priority {
+            parts.push(format!("pri:{priority}"));
+        }
+    }
+    for (key, value) in &todo.metadata {
+        parts.push(format!("{key}:{value}"));
+    }
+    parts.join(" ")
+}
+
+fn parse(line: &str, keys: &BTreeSet<&str>) -> Todo {
+    let mut todo = Todo::default();
+    let mut words = line.split_whitespace().peekable();
+
+    if words.peek() == Some(&"x") {
+        words.next();
+        todo.done = true;
+        todo.completed = words.next_if(|word| date(word).is_some()).and_then(date);
+        if todo.completed.is_some() {
+            todo.created = words.next_if(|word| date(word).is_some()).and_then(date);
+        }
+    } else {
+        todo.priority = words
+            .next_if(|word| priority(word).is_some())
+            .and_then(priority);
+        todo.created = words.next_if(|word| date(word).is_some()).and_then(date);
+    }
+
+    let mut message = Vec::new();
+    for word in words {
+        match key_value(word) {
+            Some(("pri", value)) if todo.done && letter(value).is_some() => {
+                todo.priority = letter(value);
+            }
+            Some((key, value)) if keys.contains(key) => {
+                todo.metadata.insert(key.to_string(), value.to_string());
+            }
+            _ => message.push(word),
+        }
+    }
+    todo.message = message.join(" ");
+    todo
+}
+
+fn date(word: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(word, DATE_FORMAT).ok()
+}
+
+fn priority(word: &str) -> Option<char> {
+    letter(word.strip_prefix('(')?.strip_suffix(')')?)
+}
+
+fn letter(word: &str) -> Option<char> {
+    let mut chars = word.chars();
+    match (chars.next(), chars.next()) {
+        (Some(letter), None) if letter.is_ascii_uppercase() => Some(letter),
+        _ => None,
+    }
+}
+
+/// `chave:valor`, ignorando URLs (`https://...`), horários (`12:30`) e tokens com
+/// lados vazios.
+fn key_value(word: &str) -> Option<(&str, &str)> {
+    let (key, value) = word.split_once(':')?;
+    let starts_with_letter = key.chars().next().is_some_and(char::is_alphabetic);
+    if !starts_with_letter || value.is_empty() || value.starts_with("//") || value.contains(':') {
+        return None;
+    }
+    Some((key, value))
+}
@@ -0,0 +1,504 @@
+//! Armazenamento versionado num repositório git.
+//!
+//! O arquivo de TODOs fica em `TODO_GIT_DIR` e cada alteração feita pelo
+//! `TodoStorage` vira um commit com uma mensagem descritiva (`done #4: Consulta do
+//! Zoro`), então `git log`, `git blame` e `git revert` funcionam normalmente. Com o
+//! arquivo criptografado as mensagens levam só os ids (`done #4`), sem textos nem
+//! nomes de listas e tags, que ficariam legíveis no histórico. O JSON é gravado com
+//! um campo por linha, para que o diff de cada commit mostre só o que mudou. Com
+//! `TODO_GIT_REMOTE` o repositório também é sincronizado com esse remoto: as
+//! alterações de lá são trazidas com `pull --rebase` e cada commit é enviado logo em
+//! seguida. Sem conexão os commits ficam no repositório local até o próximo envio.
+
+use crate::{
+    cli::AppError,
+    todo::{Bulk, Position, Todo, TodoList, TodoStorage, STORAGE_PATH},
+    tracking::Report,
+};
+use chrono::NaiveDate;
+use std::{env, io, path::Path, process};
+
+/// Nome do remoto configurado a partir de `TODO_GIT_REMOTE`.
+const REMOTE: &str = "origin";
+
+pub struct GitConfig {
+    pub dir: String,
+    pub remote: Option<String>,
+}
+
+impl GitConfig {
+    /// Lê `TODO_GIT_DIR` (o diretório do repositório, criado se não existir) e
+    /// `TODO_GIT_REMOTE` (a URL de um remoto, ex.: um repositório bare).
+    pub fn from_env() -> Option<Self> {
+        Some(Self {
+            dir: env::var("TODO_GIT_DIR").ok()?,
+            remote: env::var("TODO_GIT_REMOTE").ok(),
+        })
+    }
+
+    /// Caminho do arquivo de TODOs dentro do repositório.
+    pub fn path(&self) -> String {
+        Path::new(&self.dir)
+            .join(STORAGE_PATH)
+            .to_string_lossy()
+            .into_owned()
+    }
+}
+
+pub struct GitRepo {
+    dir: String,
+    synced: bool,
+}
+
+impl GitRepo {
+    /// Prepara o repositório e traz as alterações do remoto. Deve ser chamado antes
+    /// de ler o arquivo de TODOs.
+    pub async fn open(config: &GitConfig) -> Result<Self, AppError> {
+        if !Path::new(&config.dir).join(".git").exists() {
+            tokio::fs::create_dir_all(&config.dir)
+                .await
+                .map_err(AppError::Write)?;
+            git(&config.dir, &["init", "--quiet"]).await?;
+        }
+        if let Some(url) = &config.remote {
+            let command = match git(&config.dir, &["remote", "get-url", REMOTE]).await {
+                Ok(current) if current.trim() == url => None,
+                Ok(_) => Some("set-url"),
+                Err(_) => Some("add"),
+            };
+            if let Some(command) = command {
+                git(&config.dir, &["remote", command, REMOTE, url]).await?;
+            }
+        }
+        let repo = Self {
+            dir: config.dir.clone(),
+            synced: config.remote.is_some(),
+        };
+        // alterações feitas fora do programa desde a última execução
+        repo.commit("external: alterações feitas fora do todo")
+            .await?;
+        repo.pull().await?;
+        Ok(repo)
+    }
+
+    async fn git(&self, args: &[&str]) -> Result<String, AppError> {
+        git(&self.dir, args).await
+    }
+
+    /// Grava o arquivo de TODOs num commit, se ele mudou, e envia ao remoto.
+    pub async fn commit(&self, message: &str) -> Result<(), AppError> {
+        // repositório novo, o arquivo só existe depois da primeira alteração
+        if !Path::new(&self.dir).join(STORAGE_PATH).exists() {
+            return Ok(());
+        }
+        self.git(&["add", "--", STORAGE_PATH]).await?;
+        if self
+            .git(&["diff", "--cached", "--quiet", "--", STORAGE_PATH])
+            .await
+            .is_ok()
+        {
+            return Ok(());
+        }
+        let mut args = Vec::new();
+        // sem identidade configurada o git se recusa a criar o commit
+        if self.git(&["config", "user.name"]).await.is_err() {
+            args.extend(["-c", "user.name=todo", "-c", "user.email=todo@localhost"]);
+        }
+        args.extend(["commit", "--quiet", "-m", message, "--", STORAGE_PATH]);
+        self.git(&args).await?;
+        self.push().await;
+        Ok(())
+    }
+
+    /// Traz os commits do remoto. Devolve se o arquivo de TODOs mudou.
+    async fn pull(&self) -> Result<bool, AppError> {
+        if !self.synced {
+            return Ok(false);
+        }
+        let branch = self.branch().await?;
+        // remoto vazio ou inacessível: não há o que trazer agora
+        match self.git(&["ls-remote", "--heads", REMOTE, &branch]).await {
+            Ok(heads) if !heads.trim().is_empty() => (),
+            _ => return Ok(false),
+        }
+        let before = self
+            .git(&["rev-parse", "--verify", "--quiet", "HEAD"])
+            .await
+            .ok();
+        if let Err(err) = self
+            .git(&["pull", "--quiet", "--rebase", REMOTE, &branch])
+            .await
+        {
+            let _ = self.git(&["rebase", "--abort"]).await;
+            return Err(AppError::Git(format!(
+                "não foi possível juntar as alterações do remoto, resolva com `git pull` em {}: {err}",
+                self.dir
+            )));
+        }
+        let after = self
+            .git(&["rev-parse", "--verify", "--quiet", "HEAD"])
+            .await
+            .ok();
+        Ok(before != after)
+    }
+
+    /// Envia os commits ao remoto. Falhas são ignoradas: os commits continuam no
+    /// repositório local e vão no próximo envio.
+    async fn push(&self) {
+        if self.synced {
+            let _ = self.git(&["push", "--quiet", "-u", REMOTE, "HEAD"]).await;
+        }
+    }
+
+    async fn branch(&self) -> Result<String, AppError> {
+        Ok(self
+            .git(&["symbolic-ref", "--short", "HEAD"])
+            .await?
+            .trim()
+            .to_string())
+    }
+}
+
+/// Executa `git -C <dir> <args>` e devolve a saída padrão.
+async fn git(dir: &str, args: &[&str]) -> Result<String, AppError> {
+    let mut command = process::Command::new("git");
+    command.arg("-C").arg(dir).args(args);
+    let output = tokio::task::spawn_blocking(move || command.output())
+        .await
+        .map_err(|err| AppError::Git(err.to_string()))?
+        .map_err(|err| match err.kind() {
+            io::ErrorKind::NotFound => AppError::Git("o git não está instalado".to_string()),
+            _ => AppError::Git(err.to_string()),
+        })?;
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    } else {
+        Err(AppError::Git(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ))
+    }
+}
+
+pub struct GitStorage {
+    storage: Box<dyn TodoStorage>,
+    repo: GitRepo,
+}
+
+impl GitStorage {
+    pub fn new(storage: Box<dyn TodoStorage>, repo: GitRepo) -> Self {
+        Self { storage, repo }
+    }
+
+    /// Se o arquivo está criptografado e as mensagens devem ficar sem textos.
+    async fn private(&self) -> Result<bool, AppError> {
+        self.storage.encrypted().await
+    }
+}
+
+fn describe(action: &str, todo: &Todo, private: bool) -> String {
+    if private {
+        format!("{action} #{}", todo.id)
+    } else {
+        format!("{action} #{}: {}", todo.id, todo.message)
+    }
+}
+
+/// ` <texto>` para a mensagem do commit, ou nada com o arquivo criptografado.
+fn detail(text: &str, private: bool) -> String {
+    if private {
+        String::new()
+    } else {
+        format!(" {text}")
+    }
+}
+
+#[async_trait::async_trait]
+impl TodoStorage for GitStorage {
+    async fn add(&mut self, message: String) -> Result<Option<&Todo>, AppError> {
+        let private = self.private().await?;
+        let todo = self.storage.add(message).await?;
+        if let Some(todo) = todo {
+            self.repo.commit(&describe("add", todo, private)).await?;
+        }
+        Ok(todo)
+    }
+
+    async fn list(&self) -> Result<Vec<&Todo>, AppError> {
+        self.storage.list().await
+    }
+
+    async fn find(&self, key: &str) -> Result<Option<&Todo>, AppError> {
+        self.storage.find(key).await
+    }
+
+    async fn locate(&self, key: &str) -> Result<Option<&Todo>, AppError> {
+        self.storage.locate(key).await
+    }
+
+    async fn update(&mut self, id: u32, message: String) -> Result<Option<&Todo>, AppError> {
+        let private = self.private().await?;
+        let todo = self.storage.update(id, message).await?;
+        if let Some(todo) = todo {
+            self.repo.commit(&describe("update", todo, private)).await?;
+        }
+        Ok(todo)
+    }
+
+    async fn update_notes(&mut self, id: u32, notes: String) -> Result<Option<&Todo>, AppError> {
+        let private = self.private().await?;
+        let todo = self.storage.update_notes(id, notes).await?;
+        if let Some(todo) = todo {
+            self.repo.commit(&describe("notes", todo, private)).await?;
+        }
+        Ok(todo)
+    }
+
+    async fn done(&mut self, id: u32) -> Result<Option<&Todo>, AppError> {
+        let private = self.private().await?;
+        let todo = self.storage.done(id).await?;
+        if let Some(todo) = todo {
+            self.repo.commit(&describe("done", todo, private)).await?;
+        }
+        Ok(todo)
+    }
+
+    async fn delete(&mut self, id: u32) -> Result<Option<Todo>, AppError> {
+        let private = self.private().await?;
+        let todo = self.storage.delete(id).await?;
+        if let Some(todo) = &todo {
+            self.repo.commit(&describe("delete", todo, private)).await?;
+        }
+        Ok(todo)
+    }
+
+    async fn reload(&mut self) -> Result<(), AppError> {
+        self.storage.reload().await
+    }
+
+    async fn merge(&mut self) -> Result<Vec<u32>, AppError> {
+        let conflicts = self.storage.merge().await?;
+        self.repo
+            .commit("merge: alterações feitas por outro processo")
+            .await?;
+        Ok(conflicts)
+    }
+
+    /// Versiona as alterações externas e traz as do remoto.
+    async fn refresh(&mut self) -> Result<bool, AppError> {
+        let changed = self.storage.refresh().await?;
+        self.repo
+            .commit("external: alterações feitas fora do todo")
+            .await?;
+        if self.repo.pull().await? {
+            self.storage.refresh().await?;
+            return Ok(true);
+        }
+        Ok(changed)
+    }
+
+    async fn import(&mut self, todos: Vec<Todo>) -> Result<usize, AppError> {
+        let count = self.storage.import(todos).await?;
+        self.repo.commit(&format!("import: {count} TODOs")).await?;
+        Ok(count)
+    }
+
+    async fn lists(&self) -> Result<Vec<TodoList>, AppError> {
+        self.storage.lists().await
+    }
+
+    async fn current_list(&self) -> Result<String, AppError> {
+        self.storage.current_list().await
+    }
+
+    async fn switch_list(&mut self, name: &str) -> Result<bool, AppError> {
+        self.storage.switch_list(name).await
+    }
+
+    async fn create_list(&mut self, name: &str) -> Result<bool, AppError> {
+        let private = self.private().await?;
+        let created = self.storage.create_list(name).await?;
+        self.repo
+            .commit(&format!("list: cria{}", detail(name, private)))
+            .await?;
+        Ok(created)
+    }
+
+    async fn rename_list(&mut self, name: &str, new_name: &str) -> Result<bool, AppError> {
+        let private = self.private().await?;
+        let renamed = self.storage.rename_list(name, new_name).await?;
+        let names = format!("{name} para {new_name}");
+        self.repo
+            .commit(&format!("list: renomeia{}", detail(&names, private)))
+            .await?;
+        Ok(renamed)
+    }
+
+    async fn archive_list(&mut self, name: &str, archived: bool) -> Result<bool, AppError> {
+        let private = self.private().await?;
+        let changed = self.storage.archive_list(name, archived).await?;
+        let action = if archived { "arquiva" } else { "desarquiva" };
+        self.repo
+            .commit(&format!("list: {action}{}", detail(name, private)))
+            .await?;
+        Ok(changed)
+    }
+
+    async fn delete_list(&mut self, name: &str) -> Result<Option<usize>, AppError> {
+        let private = self.private().await?;
+        let deleted = self.storage.delete_list(name).await?;
+        if let Some(count) = deleted {
+            self.repo
+                .commit(&format!(
+                    "list: apaga{} e manda {count} TODOs para a lixeira",
+                    detail(name, private)
+                ))
+                .await?;
+        }
+        Ok(deleted)
+    }
+
+    async fn move_todo(&mut self, id: u32, list: &str) -> Result<Option<&Todo>, AppError> {
+        let private = self.private().await?;
+        let todo = self.storage.move_todo(id, list).await?;
+        if let Some(todo) = todo {
+            let action = format!("move{}", detail(&format!("para {list}"), private));
+            self.repo.commit(&describe(&action, todo, private)).await?;
+        }
+        Ok(todo)
+    }
+
+    async fn archive_done(&mut self, min_age_days: u32) -> Result<usize, AppError> {
+        let count = self.storage.archive_done(min_age_days).await?;
+        self.repo
+            .commit(&format!("archive: {count} TODOs feitos"))
+            .await?;
+        Ok(count)
+    }
+
+    async fn archived(&self, search: &str) -> Result<Vec<&Todo>, AppError> {
+        self.storage.archived(search).await
+    }
+
+    async fn restore(&mut self, key: &str) -> Result<Option<&Todo>, AppError> {
+        let private = self.private().await?;
+        let todo = self.storage.restore(key).await?;
+        if let Some(todo) = todo {
+            self.repo
+                .commit(&describe("restore", todo, private))
+                .await?;
+        }
+        Ok(todo)
+    }
+
+    async fn bulk(&mut self, ids: &[u32], operation: &Bulk) -> Result<usize, AppError> {
+        let private = self.private().await?;
+        let count = self.storage.bulk(ids, operation).await?;
+        let action = match operation {
+            Bulk::Done => "done".to_string(),
+            Bulk::Delete => "delete".to_string(),
+            Bulk::Tag(tag) => format!("tag{}", detail(tag, private)),
+            Bulk::Move(list) => format!("move{}", detail(&format!("para {list}"), private)),
+            Bulk::Priority(Some(priority)) => format!("priority ({priority})"),
+            Bulk::Priority(None) => "priority removida".to_string(),
+        };
+        self.repo
+            .commit(&format!("bulk {action}: {count} TODOs"))
+            .await?;
+        Ok(count)
+    }
+
+    async fn trash(&self) -> Result<Vec<&Todo>, AppError> {
+        self.storage.trash().await
+    }
+
+    async fn untrash(&mut self, key: &str) -> Result<Option<&Todo>, AppError> {
+        let private = self.private().await?;
+        let todo = self.storage.untrash(key).await?;
+        if let Some(todo) = todo {
+            self.repo
+                .commit(&describe("untrash", todo, private))
+                .await?;
+        }
+        Ok(todo)
+    }
+
+    async fn empty_trash(&mut self) -> Result<usize, AppError> {
+        let count = self.storage.empty_trash().await?;
+        self.repo
+            .commit(&format!("trash: esvazia a lixeira com {count} TODOs"))
+            .await?;
+        Ok(count)
+    }
+
+    async fn purge_trash(&mut self, retention_days: u32) -> Result<usize, AppError> {
+        let count = self.storage.purge_trash(retention_days).await?;
+        self.repo
+            .commit(&format!(
+                "trash: apaga {count} TODOs com mais de {retention_days} dias"
+            ))
+            .await?;
+        Ok(count)
+    }
+
+    async fn reorder(&mut self, id: u32, position: Position) -> Result<Option<usize>, AppError> {
+        let index = self.storage.reorder(id, position).await?;
+        if let Some(index) = index {
+            self.repo
+                .commit(&format!("reorder #{id}: posição {index}"))
+                .await?;
+        }
+        Ok(index)
+    }
+
+    async fn set_passphrase(&mut self, passphrase: Option<String>) -> Result<(), AppError> {
+        let encrypted = passphrase.is_some();
+        self.storage.set_passphrase(passphrase).await?;
+        let message = if encrypted {
+            "encrypt: troca a senha do arquivo"
+        } else {
+            "decrypt: remove a criptografia do arquivo"
+        };
+        self.repo.commit(message).await
+    }
+
+    async fn encrypted(&self) -> Result<bool, AppError> {
+        self.storage.encrypted().await
+    }
+
+    async fn start_timer(&mut self, id: u32) -> Result<Option<&Todo>, AppError> {
+        let private = self.private().await?;
+        let todo = self.storage.start_timer(id).await?;
+        if let Some(todo) = todo {
+            self.repo.commit(&describe("start", todo, private)).await?;
+        }
+        Ok(todo)
+    }
+
+    async fn stop_timer(&mut self) -> Result<Option<&Todo>, AppError> {
+        let private = self.private().await?;
+        let todo = self.storage.stop_timer().await?;
+        if let Some(todo) = todo {
+            self.repo.commit(&describe("stop", todo, private)).await?;
+        }
+        Ok(todo)
+    }
+
+    async fn running_timer(&self) -> Result<Option<&Todo>, AppError> {
+        self.storage.running_timer().await
+    }
+
+    async fn time_report(&self, from: NaiveDate, to: NaiveDate) -> Result<Report, AppError> {
+        self.storage.time_report(from, to).await
+    }
+
+    async fn complete_pomodoro(&mut self, id: u32) -> Result<Option<&Todo>, AppError> {
+        let private = self.private().await?;
+        let todo = self.storage.complete_pomodoro(id).await?;
+        if let Some(todo) = todo {
+            self.repo
+                .commit(&describe("pomodoro", todo, private))
+                .await?;
+        }
+        Ok(todo)
+    }
+}
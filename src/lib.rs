@@ -0,0 +1,20 @@
+//! TODO-CLI: o binário em `main.rs` monta o terminal e o armazenamento a partir do
+//! ambiente; os módulos ficam aqui para que os testes de integração possam montar
+//! o `TodoCli` com outras implementações de `UserInterface` e `TodoStorage`.
+
+pub mod caldav;
+pub mod cli;
+pub mod crypto;
+mod editor;
+pub mod formats;
+pub mod git;
+mod lock;
+pub mod pomodoro;
+mod prompt;
+pub mod schema;
+pub mod sync;
+pub mod terminal;
+pub mod todo;
+pub mod tracking;
+pub mod validation;
+pub mod watcher;
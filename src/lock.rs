@@ -0,0 +1,30 @@
+use crate::cli::AppError;
+use std::fs::{File, OpenOptions};
+
+/// Trava consultiva (advisory) sobre o arquivo de armazenamento.
+///
+/// A trava é feita num arquivo `<path>.lock` ao lado do armazenamento, assim a
+/// escrita do arquivo principal não depende do handle travado. Ela é liberada
+/// quando o valor sai de escopo.
+pub struct FileLock {
+    file: File,
+}
+
+impl FileLock {
+    pub fn acquire(path: &str) -> Result<Self, AppError> {
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(format!("{path}.lock"))
+            .map_err(AppError::Lock)?;
+        file.lock().map_err(AppError::Lock)?;
+        Ok(Self { file })
+    }
+}
+
+impl Drop for FileLock {
+    fn drop(&mut self) {
+        let _ = self.file.unlock();
+    }
+}
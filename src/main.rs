@@ -1,15 +1,139 @@
-mod cli;
-mod terminal;
-mod todo;
-
-use cli::TodoCli;
 use console::style;
+use std::env;
+use todo::{
+    caldav::{CalDavConfig, CalDavStorage},
+    cli::{AppError, TodoCli},
+    crypto,
+    git::{GitConfig, GitRepo, GitStorage},
+    pomodoro::Pomodoro,
+    sync,
+    terminal::{Terminal, UserInterface},
+    todo::{Position, TodoStorage, Todos, STORAGE_PATH},
+    validation::Validator,
+    watcher::FileWatcher,
+};
+
+/// Dias que um TODO fica na lixeira quando `TODO_TRASH_DAYS` não está definida.
+const TRASH_RETENTION_DAYS: u32 = 30;
+
+const USAGE: &str = "Uso: todo [sync <arquivo> | reorder <id> <cima|baixo|topo|fim|posição>]";
+
+/// Abre um arquivo de TODOs, pedindo a senha quando ele está criptografado.
+async fn open_todos(path: &str, ui: &mut Terminal) -> Result<Todos, AppError> {
+    if !Todos::is_encrypted(path).await? {
+        return Todos::open(path).await;
+    }
+    let passphrase = match crypto::passphrase_from_env()? {
+        Some(passphrase) => passphrase,
+        None => {
+            ui.ask_passphrase(&format!("Digite a senha de {path}:"))
+                .await?
+        }
+    };
+    Todos::unlock(path, &passphrase).await
+}
+
+async fn open_storage(ui: &mut Terminal) -> Result<(Box<dyn TodoStorage>, FileWatcher), AppError> {
+    // com o git o arquivo fica no repositório, atualizado antes de ser lido
+    let git = GitConfig::from_env();
+    let repo = match &git {
+        Some(config) => Some(GitRepo::open(config).await?),
+        None => None,
+    };
+    let path = match &git {
+        Some(config) => config.path(),
+        None => STORAGE_PATH.to_string(),
+    };
+    let mut todos = open_todos(&path, ui).await?;
+    if repo.is_some() {
+        todos = todos.pretty();
+    }
+    let watcher = FileWatcher::new(todos.path());
+    let mut storage: Box<dyn TodoStorage> = match CalDavConfig::from_env() {
+        Some(config) => Box::new(CalDavStorage::new(todos, config).await?),
+        None => Box::new(todos),
+    };
+    if let Some(repo) = repo {
+        storage = Box::new(GitStorage::new(storage, repo));
+    }
+    // arquiva automaticamente os TODOs feitos há mais de N dias
+    if let Some(days) = env::var("TODO_AUTO_ARCHIVE_DAYS")
+        .ok()
+        .and_then(|days| days.parse().ok())
+    {
+        storage.archive_done(days).await?;
+    }
+    // TODOs na lixeira são apagados de vez após o período de retenção
+    let retention = env::var("TODO_TRASH_DAYS")
+        .ok()
+        .and_then(|days| days.parse().ok())
+        .unwrap_or(TRASH_RETENTION_DAYS);
+    storage.purge_trash(retention).await?;
+    Ok((storage, watcher))
+}
+
+/// `todo sync <arquivo>`: junta o armazenamento local com outro arquivo. Com o git,
+/// o local é o arquivo do repositório e o resultado vira um commit.
+async fn sync(path: &str) -> Result<(), AppError> {
+    let mut terminal = Terminal::new();
+    let git = GitConfig::from_env();
+    let repo = match &git {
+        Some(config) => Some(GitRepo::open(config).await?),
+        None => None,
+    };
+    let mut local = match &git {
+        Some(config) => open_todos(&config.path(), &mut terminal).await?.pretty(),
+        None => open_todos(STORAGE_PATH, &mut terminal).await?,
+    };
+    let mut other = open_todos(path, &mut terminal).await?;
+    sync::sync(&mut terminal, &mut local, &mut other).await?;
+    match repo {
+        Some(repo) => repo.commit("sync: junta com outro arquivo").await,
+        None => Ok(()),
+    }
+}
+
+/// `todo reorder <id> <posição>`: muda a posição de um TODO na ordem manual da
+/// lista em que ele está, sem passar pelos menus.
+async fn reorder(key: &str, position: Position) -> Result<(), AppError> {
+    let mut terminal = Terminal::new();
+    let (storage, _) = open_storage(&mut terminal).await?;
+    TodoCli::new(Box::new(terminal), storage)
+        .reorder_command(key, position)
+        .await
+}
 
 #[tokio::main]
 async fn main() {
-    let storage: Box<dyn todo::TodoStorage> =
-    match todo::Todos::new().await {
-        Ok(todos) => Box::new(todos),
+    let args: Vec<String> = env::args().skip(1).collect();
+    match args.as_slice() {
+        [] => (),
+        [command, other] if command == "sync" => {
+            if let Err(err) = sync(other).await {
+                println!("\n🤨_>> Erro: {}", style(err).red());
+            }
+            return;
+        }
+        [command, key, position] if command == "reorder" => {
+            match Position::parse(position) {
+                Some(position) => {
+                    if let Err(err) = reorder(key, position).await {
+                        println!("\n🤨_>> Erro: {}", style(err).red());
+                    }
+                }
+                None => println!("{USAGE}"),
+            }
+            return;
+        }
+        _ => {
+            println!("{USAGE}");
+            return;
+        }
+    }
+
+    let mut terminal = Terminal::new();
+    let (storage, watcher) = match open_storage(&mut terminal).await {
+        Ok(opened) => opened,
         Err(err) => {
             println!(
                 "\n🤨_>> Desculpa aconteceu um erro no sistema e o sistema teve que ser encerrado.",
@@ -19,7 +143,10 @@ async fn main() {
         }
     };
 
-    let mut todo_cli = TodoCli::new(Box::new(terminal::Terminal::new()), storage);
+    let mut todo_cli = TodoCli::new(Box::new(terminal), storage)
+        .watch(watcher)
+        .validate(Validator::from_env())
+        .pomodoro(Pomodoro::from_env());
     if let Err(err) = todo_cli.run().await {
         println!(
             "\n🤨_>> Desculpa aconteceu um erro no sistema e o sistema teve que ser encerrado.",
@@ -0,0 +1,70 @@
+//! Pomodoro: blocos de trabalho num TODO separados por pausas.
+//!
+//! Cada bloco de trabalho que chega ao fim conta um pomodoro no TODO e o tempo dele
+//! entra no controle de horas. As durações vêm de `TODO_POMODORO_WORK` e
+//! `TODO_POMODORO_BREAK`, em minutos.
+
+use std::{env, fmt::Display, time::Duration};
+
+/// Minutos de trabalho quando `TODO_POMODORO_WORK` não está definida.
+const WORK_MINUTES: u64 = 25;
+
+/// Minutos de pausa quando `TODO_POMODORO_BREAK` não está definida.
+const BREAK_MINUTES: u64 = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Phase {
+    Work,
+    Break,
+}
+
+impl Display for Phase {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Work => write!(f, "trabalho"),
+            Self::Break => write!(f, "pausa"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Pomodoro {
+    pub work: Duration,
+    pub pause: Duration,
+}
+
+impl Default for Pomodoro {
+    fn default() -> Self {
+        Self {
+            work: minutes(WORK_MINUTES),
+            pause: minutes(BREAK_MINUTES),
+        }
+    }
+}
+
+impl Pomodoro {
+    pub fn from_env() -> Self {
+        let minutes_from = |name: &str, default: u64| {
+            env::var(name)
+                .ok()
+                .and_then(|minutes| minutes.parse().ok())
+                .filter(|minutes| *minutes > 0)
+                .map_or(minutes(default), minutes)
+        };
+        Self {
+            work: minutes_from("TODO_POMODORO_WORK", WORK_MINUTES),
+            pause: minutes_from("TODO_POMODORO_BREAK", BREAK_MINUTES),
+        }
+    }
+}
+
+fn minutes(minutes: u64) -> Duration {
+    Duration::from_secs(minutes * 60)
+}
+
+/// `24:59`, o tempo que falta na contagem regressiva, arredondado para cima no
+/// segundo para que o `00:00` só apareça no fim.
+pub fn format_remaining(remaining: Duration) -> String {
+    let seconds = remaining.as_secs() + u64::from(remaining.subsec_nanos() > 0);
+    format!("{:02}:{:02}", seconds / 60, seconds % 60)
+}
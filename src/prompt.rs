@@ -0,0 +1,139 @@
+//! Editor de linha das perguntas do terminal: cursor, atalhos (`Ctrl-W`, `Alt-B`,
+//! `Alt-F`...), histórico salvo entre sessões e `Tab` para completar tags e ids.
+//!
+//! Com o arquivo de TODOs criptografado o histórico não é salvo, porque guardaria
+//! os textos digitados em texto puro.
+
+use crate::{cli::AppError, todo::Todo};
+use rustyline::{
+    completion::Completer, error::ReadlineError, history::FileHistory, Config, Context, Editor,
+    Helper, Highlighter, Hinter, Validator,
+};
+use std::io;
+
+const HISTORY_PATH: &str = "todo_history.txt";
+
+pub struct LineEditor {
+    /// Fica vazio enquanto uma linha é lida na thread de `read_line`.
+    editor: Option<Editor<Words, FileHistory>>,
+    history: bool,
+}
+
+impl LineEditor {
+    /// Falha quando não é possível configurar o terminal; nesse caso as perguntas
+    /// continuam sendo lidas linha a linha.
+    pub fn new() -> Result<Self, AppError> {
+        let config = Config::builder().auto_add_history(false).build();
+        let mut editor = Editor::with_config(config).map_err(error)?;
+        editor.set_helper(Some(Words::default()));
+        // o histórico é só uma conveniência, um arquivo ausente ou inválido é ignorado
+        let _ = editor.load_history(HISTORY_PATH);
+        Ok(Self {
+            editor: Some(editor),
+            history: true,
+        })
+    }
+
+    /// Liga ou desliga o histórico; ao desligar, o histórico já salvo é apagado.
+    pub fn keep_history(&mut self, keep: bool) -> Result<(), AppError> {
+        self.history = keep;
+        if keep {
+            return Ok(());
+        }
+        if let Some(editor) = &mut self.editor {
+            let _ = editor.clear_history();
+        }
+        match std::fs::remove_file(HISTORY_PATH) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => Err(AppError::Write(err)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Palavras oferecidas pelo `Tab`: as tags `+projeto`/`@contexto` e os ids dos TODOs.
+    pub fn complete_with(&mut self, todos: &[&Todo]) {
+        let mut words: Vec<String> = todos
+            .iter()
+            .flat_map(|todo| {
+                let projects = todo.projects().into_iter().map(|tag| format!("+{tag}"));
+                let contexts = todo.contexts().into_iter().map(|tag| format!("@{tag}"));
+                projects.chain(contexts)
+            })
+            .chain(todos.iter().map(|todo| todo.id.to_string()))
+            .collect();
+        words.sort();
+        words.dedup();
+        if let Some(helper) = self.editor.as_mut().and_then(Editor::helper_mut) {
+            helper.words = words;
+        }
+    }
+
+    /// Lê uma linha já preenchida com `initial`, que o usuário pode editar. O editor
+    /// vai para uma thread separada durante a leitura para não travar o runtime.
+    pub async fn read_line(&mut self, initial: &str) -> Result<String, AppError> {
+        let mut editor = self
+            .editor
+            .take()
+            .ok_or_else(|| AppError::Stdin(io::Error::other("editor de linha indisponível")))?;
+        let initial = initial.to_string();
+        let (editor, line) = tokio::task::spawn_blocking(move || {
+            let line = editor.readline_with_initial("", (&initial, ""));
+            (editor, line)
+        })
+        .await
+        .map_err(|err| AppError::Stdin(io::Error::other(err)))?;
+        let editor = self.editor.insert(editor);
+        let line = match line {
+            Ok(line) => line.trim().to_string(),
+            Err(ReadlineError::Interrupted) => String::new(),
+            Err(err) => return Err(error(err)),
+        };
+        if self.history && !line.is_empty() {
+            let _ = editor.add_history_entry(line.as_str());
+            let _ = editor.save_history(HISTORY_PATH);
+        }
+        Ok(line)
+    }
+}
+
+fn error(err: ReadlineError) -> AppError {
+    match err {
+        ReadlineError::Io(err) => AppError::Stdin(err),
+        ReadlineError::Eof => AppError::Stdin(io::ErrorKind::UnexpectedEof.into()),
+        err => AppError::Stdin(io::Error::other(err)),
+    }
+}
+
+#[derive(Default, Helper, Hinter, Highlighter, Validator)]
+struct Words {
+    words: Vec<String>,
+}
+
+impl Completer for Words {
+    type Candidate = String;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<String>)> {
+        let length: usize = line[..pos]
+            .chars()
+            .rev()
+            .take_while(|char| !char.is_whitespace())
+            .map(char::len_utf8)
+            .sum();
+        let start = pos - length;
+        let word = &line[start..pos];
+        if word.is_empty() {
+            return Ok((pos, Vec::new()));
+        }
+        let candidates = self
+            .words
+            .iter()
+            .filter(|candidate| candidate.starts_with(word))
+            .cloned()
+            .collect();
+        Ok((start, candidates))
+    }
+}
@@ -0,0 +1,155 @@
+//! Formato versionado do arquivo de TODOs e as migrações entre versões.
+//!
+//! O arquivo é um documento `{"version": N, "sequence": ..., "todos": ..., "lists": ...}`.
+//! Arquivos antigos são atualizados na leitura, uma versão por vez, e regravados no
+//! formato atual na próxima alteração:
+//!
+//! 1. `[sequence, todos]`, a tupla original;
+//! 2. `[sequence, todos, lists]`, com as listas nomeadas;
+//! 3. o documento com `version`, em que todo TODO tem `uuid`.
+//!
+//! Campos novos com valor padrão no `Todo` não precisam de migração; ela só é
+//! necessária quando um dado antigo muda de lugar ou de significado.
+
+use crate::{
+    cli::AppError,
+    todo::{ListInfo, Todo},
+};
+use serde::{de::Error, Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+use uuid::Uuid;
+
+/// Conteúdo do arquivo: sequência de ids, TODOs e listas nomeadas.
+pub type Contents = (u32, BTreeMap<u32, Todo>, BTreeMap<String, ListInfo>);
+
+type Migration = fn(Value) -> Result<Value, serde_json::Error>;
+
+/// `MIGRATIONS[n - 1]` leva um arquivo da versão `n` para a `n + 1`.
+const MIGRATIONS: [Migration; 2] = [add_lists, add_version];
+
+/// Versão gravada pelo programa.
+pub const VERSION: u32 = MIGRATIONS.len() as u32 + 1;
+
+#[derive(Serialize)]
+struct Document<'a> {
+    version: u32,
+    sequence: u32,
+    todos: &'a BTreeMap<u32, Todo>,
+    lists: &'a BTreeMap<String, ListInfo>,
+}
+
+#[derive(Deserialize)]
+struct Stored {
+    sequence: u32,
+    #[serde(default)]
+    todos: BTreeMap<u32, Todo>,
+    #[serde(default)]
+    lists: BTreeMap<String, ListInfo>,
+}
+
+/// Lê o arquivo em qualquer versão conhecida; um arquivo vazio é uma lista vazia.
+pub fn read(contents: &str) -> Result<Contents, AppError> {
+    if contents.trim().is_empty() {
+        return Ok(Contents::default());
+    }
+    let mut value: Value = serde_json::from_str(contents).map_err(AppError::Parse)?;
+    let mut version = version(&value).map_err(AppError::Parse)?;
+    if version > VERSION {
+        return Err(AppError::NewerVersion(version));
+    }
+    while version < VERSION {
+        value = MIGRATIONS[version as usize - 1](value).map_err(AppError::Parse)?;
+        version += 1;
+    }
+    let stored: Stored = serde_json::from_value(value).map_err(AppError::Parse)?;
+    Ok((stored.sequence, stored.todos, stored.lists))
+}
+
+/// Grava no formato atual; `pretty` indenta o JSON com um campo por linha.
+pub fn write(
+    sequence: u32,
+    todos: &BTreeMap<u32, Todo>,
+    lists: &BTreeMap<String, ListInfo>,
+    pretty: bool,
+) -> Result<String, AppError> {
+    let document = Document {
+        version: VERSION,
+        sequence,
+        todos,
+        lists,
+    };
+    if pretty {
+        serde_json::to_string_pretty(&document)
+    } else {
+        serde_json::to_string(&document)
+    }
+    .map_err(AppError::Parse)
+}
+
+/// As tuplas das versões 1 e 2 não têm o campo e são reconhecidas pelo tamanho.
+fn version(value: &Value) -> Result<u32, serde_json::Error> {
+    match value {
+        Value::Array(items) if items.len() == 2 => Ok(1),
+        Value::Array(items) if items.len() == 3 => Ok(2),
+        Value::Object(document) => document
+            .get("version")
+            .and_then(Value::as_u64)
+            .and_then(|version| u32::try_from(version).ok())
+            .filter(|version| *version > 0)
+            .ok_or_else(|| serde_json::Error::custom("documento sem o campo 'version'")),
+        _ => Err(serde_json::Error::custom("formato de arquivo desconhecido")),
+    }
+}
+
+/// 1 → 2: a tupla ganha as listas nomeadas, vazias.
+fn add_lists(value: Value) -> Result<Value, serde_json::Error> {
+    let (sequence, todos): (Value, Value) = serde_json::from_value(value)?;
+    Ok(json!([sequence, todos, {}]))
+}
+
+/// 2 → 3: a tupla vira um documento com versão e todo TODO recebe um uuid.
+fn add_version(value: Value) -> Result<Value, serde_json::Error> {
+    let (sequence, mut todos, lists): (Value, Map<String, Value>, Value) =
+        serde_json::from_value(value)?;
+    for todo in todos.values_mut() {
+        let Some(todo) = todo.as_object_mut() else {
+            continue;
+        };
+        if todo
+            .get("uuid")
+            .and_then(Value::as_str)
+            .is_some_and(|uuid| !uuid.is_empty())
+        {
+            continue;
+        }
+        // TODOs importados do Taskwarrior guardavam o uuid nos metadados
+        let imported = todo
+            .get_mut("metadata")
+            .and_then(Value::as_object_mut)
+            .and_then(|metadata| metadata.remove("uuid"));
+        let uuid = match imported {
+            Some(uuid) => uuid,
+            None => Value::String(legacy_uuid(todo)),
+        };
+        todo.insert("uuid".to_string(), uuid);
+    }
+    Ok(json!({
+        "version": 3,
+        "sequence": sequence,
+        "todos": todos,
+        "lists": lists,
+    }))
+}
+
+/// Uuid de um TODO salvo antes de existir o campo. É derivado do conteúdo para que
+/// duas leituras do mesmo arquivo cheguem ao mesmo valor.
+fn legacy_uuid(todo: &Map<String, Value>) -> String {
+    let id = todo.get("id").and_then(Value::as_u64).unwrap_or_default();
+    let message = todo
+        .get("message")
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+    let name = format!("{id}:{message}");
+    Uuid::new_v5(&Uuid::NAMESPACE_OID, name.as_bytes()).to_string()
+}
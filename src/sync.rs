@@ -0,0 +1,250 @@
+//! Sincronização entre dois arquivos de TODOs alterados em paralelo (ex.: o do
+//! notebook e o de uma pasta compartilhada), pelo comando `todo sync <arquivo>`.
+//!
+//! Os TODOs são casados pelo uuid. O resultado do último sync com cada arquivo fica
+//! em `<armazenamento>.sync` e serve de base para um merge de três vias campo a
+//! campo. Sem base (primeiro sync), vence a alteração mais recente
+//! (`Todo::modified`). O que não dá para resolver sozinho é perguntado ao usuário,
+//! e os dois arquivos terminam com o mesmo conteúdo.
+
+use crate::{
+    cli::AppError,
+    terminal::{Action, UserInterface},
+    todo::{Todo, Todos},
+};
+use chrono::Utc;
+use std::{
+    cmp::Ordering,
+    collections::{btree_map::Entry, BTreeMap, BTreeSet},
+};
+
+/// As duas versões de um TODO alterado dos dois lados.
+pub struct Conflict {
+    pub local: Todo,
+    pub other: Todo,
+}
+
+/// As bases ficam em `<armazenamento>.sync`, com a mesma criptografia dele.
+const STATE_EXTENSION: &str = "sync";
+
+/// Bases do último sync, por arquivo sincronizado.
+type SyncState = BTreeMap<String, Vec<Todo>>;
+
+pub async fn sync(
+    user_interface: &mut dyn UserInterface,
+    local: &mut Todos,
+    other: &mut Todos,
+) -> Result<(), AppError> {
+    // o caminho absoluto identifica o arquivo mesmo antes de ele existir
+    let absolute = |path: &str| {
+        std::path::absolute(path)
+            .map(|path| path.display().to_string())
+            .unwrap_or_else(|_| path.to_string())
+    };
+    let key = absolute(other.path());
+    if absolute(local.path()) == key {
+        return Err(AppError::Sync(
+            "não é possível sincronizar o arquivo com ele mesmo".to_string(),
+        ));
+    }
+    let mut state: SyncState = match local.read_side(STATE_EXTENSION).await? {
+        Some(contents) => serde_json::from_str(&contents).map_err(AppError::Parse)?,
+        None => SyncState::new(),
+    };
+
+    let base = by_uuid(state.get(&key).into_iter().flatten());
+    let mine = by_uuid(local.all());
+    let theirs = by_uuid(other.all());
+    let (mut merged, conflicts) = merge(&base, &mine, &theirs);
+
+    let count = conflicts.len();
+    for conflict in conflicts {
+        let chosen = match user_interface
+            .ask_for_sync_conflict(&conflict.local, &conflict.other)
+            .await?
+        {
+            Action::KeepOther => conflict.other,
+            _ => conflict.local,
+        };
+        merged.insert(chosen.uuid.clone(), chosen);
+    }
+
+    // o que muda em algum dos lados ganha uma nova data, a mesma nos dois arquivos
+    let now = Utc::now();
+    for (uuid, todo) in merged.iter_mut() {
+        let changed = !same(Some(todo), mine.get(uuid)) || !same(Some(todo), theirs.get(uuid));
+        if changed || todo.modified.is_none() {
+            todo.modified = Some(now);
+        }
+    }
+    let (sequence, todo_list) = number(local, other, merged);
+    let mut lists = local.lists_info().clone();
+    for (name, info) in other.lists_info() {
+        lists.entry(name.clone()).or_insert_with(|| info.clone());
+    }
+    // o outro arquivo é gravado primeiro: se ele falhar, nada mudou; se o local
+    // falhar depois, o próximo sync encontra o resultado só do outro lado e o traz
+    other
+        .replace(sequence, todo_list.clone(), lists.clone())
+        .await?;
+    local.replace(sequence, todo_list, lists).await?;
+
+    state.insert(key, local.all().into_iter().cloned().collect());
+    let contents = serde_json::to_string(&state).map_err(AppError::Parse)?;
+    local.write_side(STATE_EXTENSION, &contents).await?;
+
+    user_interface
+        .show_info(&format!(
+            "{} TODOs sincronizados com {}, {count} conflitos resolvidos",
+            local.all().len(),
+            other.path()
+        ))
+        .await
+}
+
+fn by_uuid<'a>(todos: impl IntoIterator<Item = &'a Todo>) -> BTreeMap<String, Todo> {
+    todos
+        .into_iter()
+        .map(|todo| (todo.uuid.clone(), todo.clone()))
+        .collect()
+}
+
+/// Merge de três vias por uuid; devolve o resultado e os conflitos que sobraram.
+fn merge(
+    base: &BTreeMap<String, Todo>,
+    local: &BTreeMap<String, Todo>,
+    other: &BTreeMap<String, Todo>,
+) -> (BTreeMap<String, Todo>, Vec<Conflict>) {
+    let uuids: BTreeSet<&String> = base
+        .keys()
+        .chain(local.keys())
+        .chain(other.keys())
+        .collect();
+    let mut merged = BTreeMap::new();
+    let mut conflicts = Vec::new();
+    for uuid in uuids {
+        let (mine, theirs) = (local.get(uuid), other.get(uuid));
+        match resolve(base.get(uuid), mine, theirs) {
+            Some(Some(todo)) => {
+                merged.insert(uuid.clone(), todo);
+            }
+            Some(None) => (), //apagado
+            None => {
+                if let (Some(mine), Some(theirs)) = (mine, theirs) {
+                    conflicts.push(Conflict {
+                        local: mine.clone(),
+                        other: theirs.clone(),
+                    });
+                }
+            }
+        }
+    }
+    (merged, conflicts)
+}
+
+/// Versão final de um TODO, `Some(None)` se ele foi apagado ou `None` se há conflito.
+fn resolve(
+    base: Option<&Todo>,
+    mine: Option<&Todo>,
+    theirs: Option<&Todo>,
+) -> Option<Option<Todo>> {
+    if same(mine, theirs) || same(theirs, base) {
+        return Some(mine.cloned());
+    }
+    if same(mine, base) {
+        return Some(theirs.cloned());
+    }
+    match (base, mine, theirs) {
+        (Some(base), Some(mine), Some(theirs)) => merge_fields(base, mine, theirs).map(Some),
+        (None, Some(mine), Some(theirs)) => match mine.modified.cmp(&theirs.modified) {
+            Ordering::Greater => Some(Some(mine.clone())),
+            Ordering::Less => Some(Some(theirs.clone())),
+            Ordering::Equal => None,
+        },
+        // apagado de um lado e alterado do outro: a alteração é mantida
+        (_, mine, theirs) => Some(mine.or(theirs).cloned()),
+    }
+}
+
+/// Junta as alterações feitas em campos diferentes; `None` se os dois lados mudaram
+/// o mesmo campo para valores diferentes.
+fn merge_fields(base: &Todo, mine: &Todo, theirs: &Todo) -> Option<Todo> {
+    let mut merged = mine.clone();
+    let mut conflict = false;
+    macro_rules! fields {
+        ($($field:ident),*) => {$(
+            if mine.$field != theirs.$field {
+                if mine.$field == base.$field {
+                    merged.$field = theirs.$field.clone();
+                } else if theirs.$field != base.$field {
+                    conflict = true;
+                }
+            }
+        )*};
+    }
+    fields!(
+        message, done, list, priority, created, completed, archived, deleted, notes, metadata,
+        time, pomodoros
+    );
+    (!conflict).then_some(merged)
+}
+
+/// Compara ignorando o id curto, que pode ser diferente em cada arquivo.
+fn same(a: Option<&Todo>, b: Option<&Todo>) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) => {
+            Todo {
+                id: b.id,
+                ..a.clone()
+            } == *b
+        }
+        (a, b) => a.is_none() && b.is_none(),
+    }
+}
+
+/// Numera o resultado: cada TODO fica com o id que já tinha no arquivo local (ou,
+/// se veio do outro, com o id de lá); ids repetidos recebem um novo número.
+fn number(
+    local: &Todos,
+    other: &Todos,
+    merged: BTreeMap<String, Todo>,
+) -> (u32, BTreeMap<u32, Todo>) {
+    let local_ids: BTreeMap<&str, u32> = local
+        .all()
+        .into_iter()
+        .map(|todo| (todo.uuid.as_str(), todo.id))
+        .collect();
+    let other_ids: BTreeMap<&str, u32> = other
+        .all()
+        .into_iter()
+        .map(|todo| (todo.uuid.as_str(), todo.id))
+        .collect();
+    let mut sequence = local.sequence().max(other.sequence());
+    let mut todo_list = BTreeMap::new();
+    let mut renumbered = Vec::new();
+    // os TODOs que já existiam no arquivo local escolhem o id primeiro
+    let (known, new): (Vec<Todo>, Vec<Todo>) = merged
+        .into_values()
+        .partition(|todo| local_ids.contains_key(todo.uuid.as_str()));
+    for mut todo in known.into_iter().chain(new) {
+        let id = local_ids
+            .get(todo.uuid.as_str())
+            .or_else(|| other_ids.get(todo.uuid.as_str()))
+            .copied()
+            .unwrap_or(todo.id);
+        match todo_list.entry(id) {
+            Entry::Occupied(_) => renumbered.push(todo),
+            Entry::Vacant(entry) => {
+                todo.id = id;
+                sequence = sequence.max(id);
+                entry.insert(todo);
+            }
+        }
+    }
+    for mut todo in renumbered {
+        sequence += 1;
+        todo.id = sequence;
+        todo_list.insert(todo.id, todo);
+    }
+    (sequence, todo_list)
+}
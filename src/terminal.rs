@@ -1,7 +1,18 @@
-use super::todo::Todo;
-use crate::cli::AppError;
+use super::todo::{parse_date, Bulk, Position, Selection, Todo, TodoList};
+use crate::{
+    cli::AppError,
+    editor,
+    formats::{Format, Import},
+    pomodoro::{self, Phase},
+    prompt::LineEditor,
+    tracking::{self, Report},
+};
+use chrono::NaiveDate;
 use std::{thread, time::Duration};
-use tokio::io::{self, AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::{
+    io::{self, AsyncBufReadExt, AsyncWriteExt, BufReader},
+    task::JoinHandle,
+};
 
 use console::{style, Emoji, Style, Term};
 
@@ -13,21 +24,86 @@ pub enum Action {
     Done(u32),
     Delete(u32),
     Update(u32, String),
+    Merge,
+    Reload,
+    Import,
+    Export,
+    Lists,
+    SwitchList(String),
+    CreateList(String),
+    RenameList(String, String),
+    ArchiveList(String, bool),
+    DeleteList(String),
+    Move(u32, String),
+    Notes(u32),
+    Reorder(u32, Position),
+    Archive,
+    ArchiveDone,
+    SearchArchive(String),
+    Restore(String),
+    Bulk,
+    KeepLocal,
+    KeepOther,
+    Trash,
+    EmptyTrash,
+    Passphrase,
+    StartTimer(u32),
+    StopTimer,
+    Report,
+    Pomodoro(u32),
 }
 
 #[async_trait::async_trait]
 pub trait UserInterface {
     async fn input(&mut self) -> Result<String, AppError>;
+    async fn complete_with(&mut self, todos: Vec<&Todo>) -> Result<(), AppError>;
+    async fn keep_history(&mut self, keep: bool) -> Result<(), AppError>;
     async fn press_key(&mut self) -> Result<(), AppError>;
     async fn welcome(&mut self) -> Result<(), AppError>;
     async fn exit(&mut self) -> Result<(), AppError>;
-    async fn ask_for_action(&mut self) -> Result<Action, AppError>;
-    async fn ask_for_todo_action(&mut self, id: u32) -> Result<Action, AppError>;
+    async fn ask_for_action(
+        &mut self,
+        list: &str,
+        timer: Option<&Todo>,
+    ) -> Result<Action, AppError>;
+    async fn ask_for_todo_action(&mut self, todo: &Todo) -> Result<Action, AppError>;
     async fn add_todo(&mut self) -> Result<String, AppError>;
-    async fn select_todo(&mut self) -> Result<Option<u32>, AppError>;
+    async fn select_todo(&mut self) -> Result<Option<String>, AppError>;
+    async fn show_todo(&mut self, todo: &Todo) -> Result<(), AppError>;
+    async fn edit_notes(&mut self, notes: &str) -> Result<String, AppError>;
     async fn list_todo(&mut self, list: Vec<&Todo>) -> Result<(), AppError>;
+    async fn refresh_todo(&mut self, list: Vec<&Todo>) -> Result<(), AppError>;
     async fn show_sucess(&mut self, todo: &Todo, msg: &str) -> Result<(), AppError>;
     async fn show_error(&mut self, msg: &str) -> Result<(), AppError>;
+    async fn show_info(&mut self, msg: &str) -> Result<(), AppError>;
+    async fn ask_for_conflict(&mut self) -> Result<Action, AppError>;
+    async fn select_format(&mut self) -> Result<Option<Format>, AppError>;
+    async fn ask_for_path(&mut self) -> Result<String, AppError>;
+    async fn preview_import(&mut self, import: &Import) -> Result<(), AppError>;
+    async fn confirm(&mut self, msg: &str) -> Result<bool, AppError>;
+    async fn ask_for_list_action(&mut self, lists: &[TodoList]) -> Result<Action, AppError>;
+    async fn ask_for_archive_action(
+        &mut self,
+        list: Vec<&Todo>,
+        search: &str,
+    ) -> Result<Action, AppError>;
+    async fn select_todos(&mut self) -> Result<Option<Selection>, AppError>;
+    async fn ask_for_bulk_action(&mut self, count: usize) -> Result<Option<Bulk>, AppError>;
+    async fn ask_for_trash_action(&mut self, list: Vec<&Todo>) -> Result<Action, AppError>;
+    async fn ask_for_sync_conflict(
+        &mut self,
+        local: &Todo,
+        other: &Todo,
+    ) -> Result<Action, AppError>;
+    async fn ask_passphrase(&mut self, question: &str) -> Result<String, AppError>;
+    async fn ask_for_period(&mut self) -> Result<Option<(NaiveDate, NaiveDate)>, AppError>;
+    async fn show_report(&mut self, report: &Report) -> Result<(), AppError>;
+    async fn countdown(
+        &mut self,
+        todo: &Todo,
+        phase: Phase,
+        length: Duration,
+    ) -> Result<bool, AppError>;
 }
 
 pub struct Terminal {
@@ -35,6 +111,14 @@ pub struct Terminal {
     stdout: io::Stdout,
     term: Term,
     version: String,
+    pending_key: Option<JoinHandle<io::Result<char>>>,
+    line_editor: Option<LineEditor>,
+}
+
+impl Default for Terminal {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Terminal {
@@ -44,16 +128,66 @@ impl Terminal {
             stdout: io::stdout(),
             term: Term::stdout(),
             version: String::from("0.9.0"),
+            pending_key: None,
+            line_editor: LineEditor::new().ok(),
+        }
+    }
+
+    /// Lê uma tecla numa thread separada para não travar o runtime. Se a espera for
+    /// cancelada (ex.: pelo `tokio::select!` da listagem), a leitura pendente é
+    /// reaproveitada na próxima chamada e a tecla não se perde.
+    async fn read_char(&mut self) -> Result<char, AppError> {
+        let term = self.term.clone();
+        let pending = self
+            .pending_key
+            .get_or_insert_with(|| tokio::task::spawn_blocking(move || term.read_char()));
+        let result = pending.await;
+        self.pending_key = None;
+        result
+            .map_err(|err| AppError::Stdin(io::Error::other(err)))?
+            .map_err(AppError::Stdin)
+    }
+
+    async fn ask(&mut self, question: &str) -> Result<String, AppError> {
+        self.ask_with(question, "").await
+    }
+
+    /// Pergunta com a resposta já preenchida com `initial`.
+    async fn ask_with(&mut self, question: &str, initial: &str) -> Result<String, AppError> {
+        self.write_line(&format!("{} >> {question}", Emoji("😃", ":)")))
+            .await?;
+        self.input_with(initial).await
+    }
+
+    /// Lê uma linha pelo editor de linha; sem ele (ex.: entrada redirecionada), lê a
+    /// linha crua e ignora `initial`.
+    async fn input_with(&mut self, initial: &str) -> Result<String, AppError> {
+        if let Some(line_editor) = &mut self.line_editor {
+            self.stdout.flush().await.map_err(AppError::Stdout)?;
+            return line_editor.read_line(initial).await;
         }
+        let mut buffer = String::new();
+        self.stdin
+            .read_line(&mut buffer)
+            .await
+            .map_err(AppError::Stdin)?;
+
+        Ok(buffer.trim().to_string())
+    }
+
+    async fn open_editor(&mut self, text: &str) -> Result<String, AppError> {
+        self.stdout.flush().await.map_err(AppError::Stdout)?;
+        editor::edit(text).await
     }
 
     async fn write_line(&mut self, text: &str) -> Result<(), AppError> {
         let text = format!("{text}\n");
         self.stdout
-            .write(text.as_bytes())
+            .write_all(text.as_bytes())
             .await
             .map_err(AppError::Stdout)?;
-        Ok(())
+        // sem o flush a última linha pode se perder quando o programa termina
+        self.stdout.flush().await.map_err(AppError::Stdout)
     }
 
     async fn clean_screen(&mut self) -> Result<(), AppError> {
@@ -121,20 +255,27 @@ impl Terminal {
 #[async_trait::async_trait]
 impl UserInterface for Terminal {
     async fn input(&mut self) -> Result<String, AppError> {
-        //self.term.read_line().map_err(AppError::Stdin)
-        let mut buffer = String::new();
-        self.stdin
-            .read_line(&mut buffer)
-            .await
-            .map_err(AppError::Stdin)?;
+        self.input_with("").await
+    }
 
-        Ok(buffer.trim().to_string())
+    async fn complete_with(&mut self, todos: Vec<&Todo>) -> Result<(), AppError> {
+        if let Some(line_editor) = &mut self.line_editor {
+            line_editor.complete_with(&todos);
+        }
+        Ok(())
+    }
+
+    async fn keep_history(&mut self, keep: bool) -> Result<(), AppError> {
+        match &mut self.line_editor {
+            Some(line_editor) => line_editor.keep_history(keep),
+            None => Ok(()),
+        }
     }
 
     async fn press_key(&mut self) -> Result<(), AppError> {
         self.write_line("\n\n Pressione qualquer tecla para continuar ...")
             .await?;
-        self.term.read_char().map_err(AppError::Stdin)?;
+        self.read_char().await?;
         Ok(())
     }
 
@@ -182,10 +323,32 @@ impl UserInterface for Terminal {
         Ok(())
     }
 
-    async fn ask_for_action(&mut self) -> Result<Action, AppError> {
+    async fn ask_for_action(
+        &mut self,
+        list: &str,
+        timer: Option<&Todo>,
+    ) -> Result<Action, AppError> {
         self.write_line("\nAguarde ...").await?;
         thread::sleep(Duration::from_millis(2000));
         self.title("BEM VINDO AO TODO CLI").await?;
+        self.write_line(&format!(
+            "{}_>> Lista atual: {}",
+            Emoji("📂", ":)"),
+            style(list).bold().cyan()
+        ))
+        .await?;
+        if let Some(todo) = timer {
+            let elapsed = todo.time.last().map(|entry| entry.duration());
+            self.write_line(&format!(
+                "{}_>> Cronômetro: [{}] {} há {}",
+                Emoji("⏱ ", ":)"),
+                style(todo.id).magenta(),
+                style(&todo.message).bold(),
+                style(tracking::format_duration(elapsed.unwrap_or_default())).yellow()
+            ))
+            .await?;
+        }
+        self.write_line("").await?;
         self.write_line(&format!(
             "{}_>> Olá, como posso te ajudar?",
             Emoji("😃", ":)")
@@ -209,6 +372,62 @@ impl UserInterface for Terminal {
             style("e").bold().green()
         ))
         .await?;
+        self.write_line(&format!(
+            "{} >> Digite '{}' para editar vários TODOs de uma vez",
+            Emoji("🗂 ", ":)"),
+            style("v").bold().green()
+        ))
+        .await?;
+        self.write_line(&format!(
+            "{} >> Digite '{}' para trocar ou gerenciar as listas",
+            Emoji("📂", ":)"),
+            style("m").bold().green()
+        ))
+        .await?;
+        self.write_line(&format!(
+            "{} >> Digite '{}' para ver o arquivo de TODOs concluídos",
+            Emoji("📦", ":)"),
+            style("h").bold().green()
+        ))
+        .await?;
+        self.write_line(&format!(
+            "{} >> Digite '{}' para abrir a lixeira",
+            Emoji("🗑 ", ":)"),
+            style("d").bold().green()
+        ))
+        .await?;
+        if timer.is_some() {
+            self.write_line(&format!(
+                "{} >> Digite '{}' para parar o cronômetro",
+                Emoji("⏹ ", ":)"),
+                style("c").bold().green()
+            ))
+            .await?;
+        }
+        self.write_line(&format!(
+            "{} >> Digite '{}' para ver o relatório de horas",
+            Emoji("📊", ":)"),
+            style("r").bold().green()
+        ))
+        .await?;
+        self.write_line(&format!(
+            "{} >> Digite '{}' para proteger o arquivo com senha",
+            Emoji("🔒", ":)"),
+            style("s").bold().green()
+        ))
+        .await?;
+        self.write_line(&format!(
+            "{} >> Digite '{}' para importar TODOs de um arquivo",
+            Emoji("📥", ":)"),
+            style("i").bold().green()
+        ))
+        .await?;
+        self.write_line(&format!(
+            "{} >> Digite '{}' para exportar os TODOs para um arquivo",
+            Emoji("📤", ":)"),
+            style("o").bold().green()
+        ))
+        .await?;
         self.write_line(&format!(
             "{} >> Digite '{}' para sair",
             Emoji("👋", ":)"),
@@ -216,12 +435,21 @@ impl UserInterface for Terminal {
         ))
         .await?;
         loop {
-            let answer = self.term.read_char().map_err(AppError::Stdin)?;
+            let answer = self.read_char().await?;
             self.write_line("").await?; //para quebrar a linha após a resposta
             match answer {
                 'a' => return Ok(Action::Add),
                 'l' => return Ok(Action::List),
                 'e' => return Ok(Action::Edit),
+                'v' => return Ok(Action::Bulk),
+                'm' => return Ok(Action::Lists),
+                'h' => return Ok(Action::Archive),
+                'd' => return Ok(Action::Trash),
+                'c' if timer.is_some() => return Ok(Action::StopTimer),
+                'r' => return Ok(Action::Report),
+                's' => return Ok(Action::Passphrase),
+                'i' => return Ok(Action::Import),
+                'o' => return Ok(Action::Export),
                 'x' => return Ok(Action::Exit),
                 _ => {
                     self.write_line(&format!(
@@ -234,7 +462,8 @@ impl UserInterface for Terminal {
         }
     }
 
-    async fn ask_for_todo_action(&mut self, id: u32) -> Result<Action, AppError> {
+    async fn ask_for_todo_action(&mut self, todo: &Todo) -> Result<Action, AppError> {
+        let id = todo.id;
         loop {
             self.write_line(&format!(
                 "{} >> Digite '{}' para marcar como feito",
@@ -243,11 +472,42 @@ impl UserInterface for Terminal {
             ))
             .await?;
             self.write_line(&format!(
-                "{} >> Digite '{}' para editar",
+                "{} >> Digite '{}' para editar o texto no $EDITOR",
                 Emoji("📝", ":)"),
                 style("e").bold().green()
             ))
             .await?;
+            self.write_line(&format!(
+                "{} >> Digite '{}' para editar as anotações no $EDITOR",
+                Emoji("🗒 ", ":)"),
+                style("n").bold().green()
+            ))
+            .await?;
+            self.write_line(&format!(
+                "{} >> Digite '{}' para mover para outra lista",
+                Emoji("📂", ":)"),
+                style("m").bold().green()
+            ))
+            .await?;
+            let timer = if todo.running() { "parar" } else { "iniciar" };
+            self.write_line(&format!(
+                "{} >> Digite '{}' para {timer} o cronômetro",
+                Emoji("⏱ ", ":)"),
+                style("t").bold().green()
+            ))
+            .await?;
+            self.write_line(&format!(
+                "{} >> Digite '{}' para fazer um pomodoro",
+                Emoji("🍅", ":)"),
+                style("o").bold().green()
+            ))
+            .await?;
+            self.write_line(&format!(
+                "{} >> Digite '{}' para mudar a posição na lista",
+                Emoji("↕️ ", ":)"),
+                style("p").bold().green()
+            ))
+            .await?;
             self.write_line(&format!(
                 "{} >> Digite '{}' para deletar",
                 Emoji("🗑 ", ":)"),
@@ -261,19 +521,56 @@ impl UserInterface for Terminal {
             ))
             .await?;
 
-            let answer = self.term.read_char().map_err(AppError::Stdin)?;
+            let answer = self.read_char().await?;
             match answer {
                 'f' => return Ok(Action::Done(id)),
                 'e' => {
-                    self.write_line(&format!(
-                        "{} >> Digite o novo texto do TODO",
-                        Emoji("😃", ":)")
-                    ))
-                    .await?;
-                    let text = self.input().await?;
+                    let text = match self.open_editor(&todo.message).await {
+                        // o TODO tem uma linha só: quebras de linha viram espaços
+                        Ok(text) => text
+                            .lines()
+                            .map(str::trim)
+                            .filter(|line| !line.is_empty())
+                            .collect::<Vec<_>>()
+                            .join(" "),
+                        Err(err @ AppError::Editor(_)) => {
+                            self.show_error(&err.to_string()).await?;
+                            self.ask_with("Digite o novo texto do TODO", &todo.message)
+                                .await?
+                        }
+                        Err(err) => return Err(err),
+                    };
                     return Ok(Action::Update(id, text));
                 }
-                'd' => return Ok(Action::Delete(id)),
+                'n' => return Ok(Action::Notes(id)),
+                't' if todo.running() => return Ok(Action::StopTimer),
+                't' => return Ok(Action::StartTimer(id)),
+                'o' => return Ok(Action::Pomodoro(id)),
+                'p' => {
+                    let position = self
+                        .ask("Para onde? (c)ima, (b)aixo, (t)opo, (f)im ou o número da posição")
+                        .await?;
+                    match Position::parse(&position) {
+                        Some(position) => return Ok(Action::Reorder(id, position)),
+                        None => {
+                            self.write_line(&format!(
+                                "{}_>> {}",
+                                Emoji("😕", ":/"),
+                                style("A posição informada é inválida").red()
+                            ))
+                            .await?
+                        }
+                    }
+                }
+                'm' => {
+                    let list = self.ask("Para qual lista deseja mover o TODO?").await?;
+                    return Ok(Action::Move(id, list));
+                }
+                'd' => {
+                    if self.confirm("Deseja mover o TODO para a lixeira?").await? {
+                        return Ok(Action::Delete(id));
+                    }
+                }
                 'x' => return Ok(Action::Exit),
                 _ => {
                     self.write_line(&format!(
@@ -298,18 +595,71 @@ impl UserInterface for Terminal {
         Ok(message)
     }
 
-    async fn select_todo(&mut self) -> Result<Option<u32>, AppError> {
+    async fn select_todo(&mut self) -> Result<Option<String>, AppError> {
         self.write_line(&format!(
-            "\n\n {} >> Informe a chave do Todo que deseja acessar: ",
+            "\n\n {} >> Informe a chave (ou o uuid) do Todo que deseja acessar: ",
             Emoji("😃", ":)")
         ))
         .await?;
         let input = self.input().await?;
-        if let Ok(id) = input.parse::<u32>() {
-            Ok(Some(id))
-        } else {
-            Ok(None)
+        Ok(Some(input).filter(|input| !input.is_empty()))
+    }
+
+    async fn show_todo(&mut self, todo: &Todo) -> Result<(), AppError> {
+        self.title("DETALHES DO TODO").await?;
+        self.write_line(&format!(
+            "[{}] {}",
+            style(todo.id).magenta(),
+            style(&todo.message).bold()
+        ))
+        .await?;
+        let status = if todo.done { "feito" } else { "pendente" };
+        self.write_line(&format!("Status: {status}")).await?;
+        self.write_line(&format!("Uuid: {}", style(&todo.uuid).dim()))
+            .await?;
+        if let Some(priority) = todo.priority {
+            self.write_line(&format!("Prioridade: {priority}")).await?;
         }
+        if let Some(created) = todo.created {
+            self.write_line(&format!("Criado em: {created}")).await?;
+        }
+        if let Some(completed) = todo.completed {
+            self.write_line(&format!("Concluído em: {completed}"))
+                .await?;
+        }
+        for (key, value) in &todo.metadata {
+            self.write_line(&format!("{key}: {value}")).await?;
+        }
+        if todo.pomodoros > 0 {
+            self.write_line(&format!("Pomodoros: {}", todo.pomodoros))
+                .await?;
+        }
+        if !todo.time.is_empty() {
+            let running = if todo.running() {
+                " (cronômetro em andamento)"
+            } else {
+                ""
+            };
+            self.write_line(&format!(
+                "Tempo registrado: {}{running}",
+                tracking::format_duration(todo.time_spent())
+            ))
+            .await?;
+        }
+        if !todo.notes.is_empty() {
+            self.write_line(&format!("\n{}", style("Anotações:").bold()))
+                .await?;
+            for line in todo.notes.lines() {
+                self.write_line(&format!("  {line}")).await?;
+            }
+        }
+        self.write_line("").await?;
+        Ok(())
+    }
+
+    async fn edit_notes(&mut self, notes: &str) -> Result<String, AppError> {
+        let notes = self.open_editor(notes).await?;
+        Ok(notes.trim_end().to_string())
     }
 
     async fn list_todo(&mut self, list: Vec<&Todo>) -> Result<(), AppError> {
@@ -329,8 +679,14 @@ impl UserInterface for Terminal {
                     Style::new().blue()
                 };
 
+                // só um indicador: as anotações aparecem na visão de detalhes
+                let notes = if todo.notes.is_empty() { "" } else { " 🗒" };
+                let pomodoros = match todo.pomodoros {
+                    0 => String::new(),
+                    count => format!(" 🍅{count}"),
+                };
                 self.write_line(&format!(
-                    "{} - [{}] {}",
+                    "{} - [{}] {}{notes}{pomodoros}",
                     Emoji("✅", ":)"),
                     color.apply_to(&todo.id),
                     color.apply_to(&todo.message)
@@ -348,6 +704,17 @@ impl UserInterface for Terminal {
         Ok(())
     }
 
+    async fn refresh_todo(&mut self, list: Vec<&Todo>) -> Result<(), AppError> {
+        self.list_todo(list).await?;
+        self.write_line(&format!(
+            "\n{}_>> {}",
+            Emoji("🔄", ":)"),
+            style("O arquivo foi alterado por outro processo, lista atualizada").yellow()
+        ))
+        .await?;
+        Ok(())
+    }
+
     async fn show_sucess(&mut self, todo: &Todo, msg: &str) -> Result<(), AppError> {
         self.write_line(&format!("\n{}_>> O TODO: \n", Emoji("😃", ":)")))
             .await?;
@@ -366,4 +733,664 @@ impl UserInterface for Terminal {
             .await?;
         Ok(())
     }
+
+    async fn show_info(&mut self, msg: &str) -> Result<(), AppError> {
+        self.write_line(&format!("{}_>> {}", Emoji("😃", ":)"), style(msg).green()))
+            .await?;
+        Ok(())
+    }
+
+    async fn ask_for_conflict(&mut self) -> Result<Action, AppError> {
+        self.write_line(&format!(
+            "{} >> Digite '{}' para mesclar as suas alterações com as do arquivo",
+            Emoji("🔀", ":)"),
+            style("m").bold().green()
+        ))
+        .await?;
+        self.write_line(&format!(
+            "{} >> Digite '{}' para recarregar o arquivo e descartar a sua alteração",
+            Emoji("🔄", ":)"),
+            style("r").bold().red()
+        ))
+        .await?;
+        loop {
+            let answer = self.read_char().await?;
+            match answer {
+                'm' => return Ok(Action::Merge),
+                'r' => return Ok(Action::Reload),
+                _ => {
+                    self.write_line(&format!(
+                        "{}_>> Desculpa eu não entendi.",
+                        Emoji("🤨", ":/")
+                    ))
+                    .await?
+                }
+            }
+        }
+    }
+
+    async fn select_format(&mut self) -> Result<Option<Format>, AppError> {
+        self.write_line(&format!("\n{} >> Escolha o formato:", Emoji("😃", ":)")))
+            .await?;
+        for (index, format) in Format::ALL.iter().enumerate() {
+            self.write_line(&format!(
+                "   {} - {}",
+                style(index + 1).bold().green(),
+                format.name()
+            ))
+            .await?;
+        }
+        let input = self.input().await?;
+        Ok(input
+            .parse::<usize>()
+            .ok()
+            .and_then(|index| index.checked_sub(1))
+            .and_then(|index| Format::ALL.get(index).copied()))
+    }
+
+    async fn ask_for_path(&mut self) -> Result<String, AppError> {
+        self.write_line(&format!(
+            "{} >> Informe o caminho do arquivo: ",
+            Emoji("😃", ":)")
+        ))
+        .await?;
+        self.input().await
+    }
+
+    async fn preview_import(&mut self, import: &Import) -> Result<(), AppError> {
+        self.title("PRÉ-VISUALIZAÇÃO DA IMPORTAÇÃO").await?;
+        for todo in &import.todos {
+            let label = if todo.id == 0 {
+                style("novo".to_string()).green()
+            } else {
+                style(format!("atualiza {}", todo.id)).yellow()
+            };
+            self.write_line(&format!(
+                "{} - [{}] {}",
+                Emoji("📥", ":)"),
+                label,
+                style(&todo.message).blue()
+            ))
+            .await?;
+        }
+        if !import.errors.is_empty() {
+            self.write_line(&format!(
+                "\n{}_>> {} registros serão ignorados:",
+                Emoji("😕", ":/"),
+                style(import.errors.len()).red()
+            ))
+            .await?;
+            for error in &import.errors {
+                self.write_line(&format!("   {}", style(error).red()))
+                    .await?;
+            }
+        }
+        if !import.warnings.is_empty() {
+            self.write_line(&format!(
+                "\n{}_>> {} campos não puderam ser mapeados:",
+                Emoji("🤔", ":/"),
+                style(import.warnings.len()).yellow()
+            ))
+            .await?;
+            for warning in &import.warnings {
+                self.write_line(&format!("   {}", style(warning).yellow()))
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn confirm(&mut self, msg: &str) -> Result<bool, AppError> {
+        self.write_line(&format!(
+            "\n{} >> {} ('{}' para sim, '{}' para não)",
+            Emoji("🤔", "?"),
+            msg,
+            style("s").bold().green(),
+            style("n").bold().red()
+        ))
+        .await?;
+        loop {
+            match self.read_char().await? {
+                's' => return Ok(true),
+                'n' => return Ok(false),
+                _ => {
+                    self.write_line(&format!(
+                        "{}_>> Desculpa eu não entendi.",
+                        Emoji("🤨", ":/")
+                    ))
+                    .await?
+                }
+            }
+        }
+    }
+
+    async fn ask_for_list_action(&mut self, lists: &[TodoList]) -> Result<Action, AppError> {
+        self.title("LISTAS").await?;
+        for list in lists {
+            let color = if list.archived {
+                Style::new().dim()
+            } else {
+                Style::new().blue()
+            };
+            self.write_line(&format!(
+                "{} {} ({} TODOs){}",
+                if list.current { "👉" } else { "  " },
+                color.apply_to(&list.name),
+                list.count,
+                if list.archived { " - arquivada" } else { "" }
+            ))
+            .await?;
+        }
+        self.write_line("").await?;
+        self.write_line(&format!(
+            "{} >> Digite '{}' para trocar de lista",
+            Emoji("📂", ":)"),
+            style("t").bold().green()
+        ))
+        .await?;
+        self.write_line(&format!(
+            "{} >> Digite '{}' para criar uma lista",
+            Emoji("✅", ":)"),
+            style("c").bold().green()
+        ))
+        .await?;
+        self.write_line(&format!(
+            "{} >> Digite '{}' para renomear uma lista",
+            Emoji("📝", ":)"),
+            style("r").bold().green()
+        ))
+        .await?;
+        self.write_line(&format!(
+            "{} >> Digite '{}' para arquivar/desarquivar uma lista",
+            Emoji("📦", ":)"),
+            style("a").bold().green()
+        ))
+        .await?;
+        self.write_line(&format!(
+            "{} >> Digite '{}' para deletar uma lista, com os TODOs indo para a lixeira",
+            Emoji("🗑 ", ":)"),
+            style("d").bold().green()
+        ))
+        .await?;
+        self.write_line(&format!(
+            "{} >> Digite '{}' para voltar",
+            Emoji("👈", ":)"),
+            style("x").bold().red()
+        ))
+        .await?;
+        loop {
+            let answer = self.read_char().await?;
+            match answer {
+                't' => return Ok(Action::SwitchList(self.ask("Qual lista?").await?)),
+                'c' => return Ok(Action::CreateList(self.ask("Nome da nova lista:").await?)),
+                'r' => {
+                    let name = self.ask("Qual lista deseja renomear?").await?;
+                    let new_name = self.ask_with("Novo nome da lista:", &name).await?;
+                    return Ok(Action::RenameList(name, new_name));
+                }
+                'a' => {
+                    let name = self.ask("Qual lista deseja arquivar/desarquivar?").await?;
+                    let archived = lists
+                        .iter()
+                        .find(|list| list.name == name)
+                        .is_some_and(|list| list.archived);
+                    return Ok(Action::ArchiveList(name, !archived));
+                }
+                'd' => return Ok(Action::DeleteList(self.ask("Qual lista?").await?)),
+                'x' => return Ok(Action::Exit),
+                _ => {
+                    self.write_line(&format!(
+                        "{}_>> Desculpa eu não entendi.",
+                        Emoji("🤨", ":/")
+                    ))
+                    .await?
+                }
+            }
+        }
+    }
+
+    async fn ask_for_archive_action(
+        &mut self,
+        list: Vec<&Todo>,
+        search: &str,
+    ) -> Result<Action, AppError> {
+        self.title("ARQUIVO DE TODOS CONCLUÍDOS").await?;
+        if !search.is_empty() {
+            self.write_line(&format!(
+                "{}_>> Buscando por '{}'",
+                Emoji("🔎", ":)"),
+                style(search).bold()
+            ))
+            .await?;
+        }
+        if list.is_empty() {
+            self.write_line(&format!(
+                "{}_>> {}",
+                Emoji("😃", ":)"),
+                style("Nenhum TODO arquivado").red()
+            ))
+            .await?;
+        }
+        for todo in list {
+            self.write_line(&format!(
+                "{} - [{}] {} {}",
+                Emoji("📦", ":)"),
+                style(todo.id).magenta(),
+                style(&todo.message).magenta(),
+                style(
+                    todo.archived
+                        .map(|date| date.to_string())
+                        .unwrap_or_default()
+                )
+                .dim()
+            ))
+            .await?;
+        }
+        self.write_line("").await?;
+        self.write_line(&format!(
+            "{} >> Digite '{}' para arquivar todos os TODOs feitos",
+            Emoji("✅", ":)"),
+            style("f").bold().green()
+        ))
+        .await?;
+        self.write_line(&format!(
+            "{} >> Digite '{}' para buscar no arquivo",
+            Emoji("🔎", ":)"),
+            style("b").bold().green()
+        ))
+        .await?;
+        self.write_line(&format!(
+            "{} >> Digite '{}' para restaurar um TODO",
+            Emoji("♻️ ", ":)"),
+            style("r").bold().green()
+        ))
+        .await?;
+        self.write_line(&format!(
+            "{} >> Digite '{}' para voltar",
+            Emoji("👈", ":)"),
+            style("x").bold().red()
+        ))
+        .await?;
+        loop {
+            let answer = self.read_char().await?;
+            match answer {
+                'f' => return Ok(Action::ArchiveDone),
+                'b' => {
+                    let search = self.ask("O que deseja buscar? (vazio mostra tudo)").await?;
+                    return Ok(Action::SearchArchive(search));
+                }
+                'r' => {
+                    let id = self
+                        .ask("Informe a chave do TODO que deseja restaurar:")
+                        .await?;
+                    return Ok(Action::Restore(id));
+                }
+                'x' => return Ok(Action::Exit),
+                _ => {
+                    self.write_line(&format!(
+                        "{}_>> Desculpa eu não entendi.",
+                        Emoji("🤨", ":/")
+                    ))
+                    .await?
+                }
+            }
+        }
+    }
+
+    async fn select_todos(&mut self) -> Result<Option<Selection>, AppError> {
+        self.write_line(&format!(
+            "\n\n {} >> Informe as chaves dos TODOs (ex.: 3-7 ou 1,4,9) ou um texto para filtrar: ",
+            Emoji("😃", ":)")
+        ))
+        .await?;
+        let input = self.input().await?;
+        Ok(Selection::parse(&input))
+    }
+
+    async fn ask_for_bulk_action(&mut self, count: usize) -> Result<Option<Bulk>, AppError> {
+        self.write_line(&format!(
+            "\n{}_>> {} TODOs selecionados",
+            Emoji("🗂 ", ":)"),
+            style(count).bold()
+        ))
+        .await?;
+        self.write_line(&format!(
+            "{} >> Digite '{}' para marcar todos como feitos",
+            Emoji("✅", ":)"),
+            style("f").bold().green()
+        ))
+        .await?;
+        self.write_line(&format!(
+            "{} >> Digite '{}' para adicionar uma tag (+projeto ou @contexto)",
+            Emoji("🏷 ", ":)"),
+            style("t").bold().green()
+        ))
+        .await?;
+        self.write_line(&format!(
+            "{} >> Digite '{}' para mover para outra lista",
+            Emoji("📂", ":)"),
+            style("m").bold().green()
+        ))
+        .await?;
+        self.write_line(&format!(
+            "{} >> Digite '{}' para alterar a prioridade",
+            Emoji("🔝", ":)"),
+            style("p").bold().green()
+        ))
+        .await?;
+        self.write_line(&format!(
+            "{} >> Digite '{}' para deletar",
+            Emoji("🗑 ", ":)"),
+            style("d").bold().green()
+        ))
+        .await?;
+        self.write_line(&format!(
+            "{} >> Digite '{}' para voltar",
+            Emoji("👈", ":)"),
+            style("x").bold().red()
+        ))
+        .await?;
+        loop {
+            let answer = self.read_char().await?;
+            match answer {
+                'f' => return Ok(Some(Bulk::Done)),
+                't' => {
+                    let tag = self
+                        .ask("Qual tag deseja adicionar? (+projeto ou @contexto)")
+                        .await?;
+                    let tag = tag.trim();
+                    if tag.len() > 1 && !tag.contains(char::is_whitespace) {
+                        if tag.starts_with('+') || tag.starts_with('@') {
+                            return Ok(Some(Bulk::Tag(tag.to_string())));
+                        }
+                        return Ok(Some(Bulk::Tag(format!("+{tag}"))));
+                    }
+                    self.write_line(&format!(
+                        "{}_>> {}",
+                        Emoji("😕", ":/"),
+                        style("A tag informada é inválida").red()
+                    ))
+                    .await?
+                }
+                'm' => {
+                    let list = self.ask("Para qual lista deseja mover os TODOs?").await?;
+                    return Ok(Some(Bulk::Move(list)));
+                }
+                'p' => {
+                    let priority = self
+                        .ask("Qual a nova prioridade? (A-Z, vazio remove a prioridade)")
+                        .await?;
+                    let mut chars = priority.trim().chars();
+                    match (chars.next(), chars.next()) {
+                        (None, _) => return Ok(Some(Bulk::Priority(None))),
+                        (Some(letter), None) if letter.is_ascii_alphabetic() => {
+                            return Ok(Some(Bulk::Priority(Some(letter.to_ascii_uppercase()))))
+                        }
+                        _ => {
+                            self.write_line(&format!(
+                                "{}_>> {}",
+                                Emoji("😕", ":/"),
+                                style("A prioridade informada é inválida").red()
+                            ))
+                            .await?
+                        }
+                    }
+                }
+                'd' => return Ok(Some(Bulk::Delete)),
+                'x' => return Ok(None),
+                _ => {
+                    self.write_line(&format!(
+                        "{}_>> Desculpa eu não entendi.",
+                        Emoji("🤨", ":/")
+                    ))
+                    .await?
+                }
+            }
+        }
+    }
+
+    async fn ask_for_trash_action(&mut self, list: Vec<&Todo>) -> Result<Action, AppError> {
+        self.title("LIXEIRA").await?;
+        if list.is_empty() {
+            self.write_line(&format!(
+                "{}_>> {}",
+                Emoji("😃", ":)"),
+                style("A lixeira está vazia").red()
+            ))
+            .await?;
+        }
+        for todo in list {
+            self.write_line(&format!(
+                "{} - [{}] {} {}",
+                Emoji("🗑 ", ":)"),
+                style(todo.id).magenta(),
+                style(&todo.message).magenta(),
+                style(
+                    todo.deleted
+                        .map(|date| date.to_string())
+                        .unwrap_or_default()
+                )
+                .dim()
+            ))
+            .await?;
+        }
+        self.write_line("").await?;
+        self.write_line(&format!(
+            "{} >> Digite '{}' para restaurar um TODO",
+            Emoji("♻️ ", ":)"),
+            style("r").bold().green()
+        ))
+        .await?;
+        self.write_line(&format!(
+            "{} >> Digite '{}' para esvaziar a lixeira",
+            Emoji("🔥", ":)"),
+            style("e").bold().green()
+        ))
+        .await?;
+        self.write_line(&format!(
+            "{} >> Digite '{}' para voltar",
+            Emoji("👈", ":)"),
+            style("x").bold().red()
+        ))
+        .await?;
+        loop {
+            let answer = self.read_char().await?;
+            match answer {
+                'r' => {
+                    let id = self
+                        .ask("Informe a chave do TODO que deseja restaurar:")
+                        .await?;
+                    return Ok(Action::Restore(id));
+                }
+                'e' => return Ok(Action::EmptyTrash),
+                'x' => return Ok(Action::Exit),
+                _ => {
+                    self.write_line(&format!(
+                        "{}_>> Desculpa eu não entendi.",
+                        Emoji("🤨", ":/")
+                    ))
+                    .await?
+                }
+            }
+        }
+    }
+
+    async fn ask_for_sync_conflict(
+        &mut self,
+        local: &Todo,
+        other: &Todo,
+    ) -> Result<Action, AppError> {
+        self.show_todo(local).await?;
+        self.write_line(&format!(
+            "{}_>> {}",
+            Emoji("⚠️ ", ":/"),
+            style("Este TODO foi alterado nos dois arquivos. Versão do outro arquivo:").yellow()
+        ))
+        .await?;
+        self.write_line(&format!(
+            "[{}] {} ({})",
+            style(other.id).magenta(),
+            style(&other.message).bold(),
+            if other.done { "feito" } else { "pendente" }
+        ))
+        .await?;
+        for line in other.notes.lines() {
+            self.write_line(&format!("  {line}")).await?;
+        }
+        self.write_line(&format!(
+            "\n{} >> Digite '{}' para manter a versão local ou '{}' para a do outro arquivo",
+            Emoji("🤔", "?"),
+            style("l").bold().green(),
+            style("o").bold().green()
+        ))
+        .await?;
+        loop {
+            match self.read_char().await? {
+                'l' => return Ok(Action::KeepLocal),
+                'o' => return Ok(Action::KeepOther),
+                _ => {
+                    self.write_line(&format!(
+                        "{}_>> Desculpa eu não entendi.",
+                        Emoji("🤨", ":/")
+                    ))
+                    .await?
+                }
+            }
+        }
+    }
+
+    /// Lê a senha sem mostrá-la; com a entrada redirecionada, lê a linha crua.
+    async fn ask_passphrase(&mut self, question: &str) -> Result<String, AppError> {
+        self.write_line(&format!("{} >> {question}", Emoji("🔑", ":)")))
+            .await?;
+        if self.line_editor.is_none() {
+            return self.input_with("").await;
+        }
+        let term = self.term.clone();
+        tokio::task::spawn_blocking(move || term.read_secure_line())
+            .await
+            .map_err(|err| AppError::Stdin(io::Error::other(err)))?
+            .map_err(AppError::Stdin)
+    }
+
+    /// Pergunta as datas do relatório, com o mês atual já preenchido; `None` se
+    /// alguma data for inválida ou o início vier depois do fim.
+    async fn ask_for_period(&mut self) -> Result<Option<(NaiveDate, NaiveDate)>, AppError> {
+        self.title("RELATÓRIO DE HORAS").await?;
+        let (from, to) = tracking::this_month();
+        let from = self
+            .ask_with("Data inicial (AAAA-MM-DD ou DD/MM/AAAA)", &from.to_string())
+            .await?;
+        let to = self
+            .ask_with("Data final (AAAA-MM-DD ou DD/MM/AAAA)", &to.to_string())
+            .await?;
+        Ok(parse_date(&from)
+            .zip(parse_date(&to))
+            .filter(|(from, to)| from <= to))
+    }
+
+    async fn show_report(&mut self, report: &Report) -> Result<(), AppError> {
+        self.title("RELATÓRIO DE HORAS").await?;
+        self.write_line(&format!(
+            "{}_>> De {} a {}: {}",
+            Emoji("📊", ":)"),
+            report.from.format("%d/%m/%Y"),
+            report.to.format("%d/%m/%Y"),
+            style(tracking::format_duration(report.total))
+                .bold()
+                .yellow()
+        ))
+        .await?;
+        self.write_line(&format!("\n{}", style("Por TODO:").bold()))
+            .await?;
+        for (id, message, spent) in &report.todos {
+            self.write_line(&format!(
+                "  {:>9}  [{}] {}",
+                tracking::format_duration(*spent),
+                style(id).magenta(),
+                message
+            ))
+            .await?;
+        }
+        self.write_line(&format!("\n{}", style("Por tag:").bold()))
+            .await?;
+        for (tag, spent) in &report.tags {
+            self.write_line(&format!(
+                "  {:>9}  {}",
+                tracking::format_duration(*spent),
+                style(tag).cyan()
+            ))
+            .await?;
+        }
+        self.write_line(&format!("\n{}", style("Por dia:").bold()))
+            .await?;
+        for (day, spent) in &report.days {
+            self.write_line(&format!(
+                "  {:>9}  {}",
+                tracking::format_duration(*spent),
+                day.format("%d/%m/%Y")
+            ))
+            .await?;
+        }
+        self.write_line("").await?;
+        Ok(())
+    }
+
+    /// Contagem regressiva de uma fase do pomodoro, atualizada a cada segundo. Toca
+    /// o sino do terminal no fim; qualquer tecla interrompe e devolve `false`.
+    async fn countdown(
+        &mut self,
+        todo: &Todo,
+        phase: Phase,
+        length: Duration,
+    ) -> Result<bool, AppError> {
+        self.title("POMODORO").await?;
+        self.write_line(&format!(
+            "{}_>> [{}] {}",
+            Emoji("🍅", ":)"),
+            style(todo.id).magenta(),
+            style(&todo.message).bold()
+        ))
+        .await?;
+        self.write_line(&format!(
+            "{} >> Pressione qualquer tecla para interromper\n",
+            Emoji("👈", ":)")
+        ))
+        .await?;
+        self.write_line("").await?;
+        let end = tokio::time::Instant::now() + length;
+        let mut ticks = tokio::time::interval(Duration::from_secs(1));
+        self.term.hide_cursor().map_err(AppError::Stdout)?;
+        let finished = loop {
+            let remaining = end.saturating_duration_since(tokio::time::Instant::now());
+            self.term.clear_last_lines(1).map_err(AppError::Stdout)?;
+            self.term
+                .write_line(&format!(
+                    "{} >> {}: {}",
+                    Emoji("⏱ ", ":)"),
+                    style(phase).bold(),
+                    style(pomodoro::format_remaining(remaining)).yellow()
+                ))
+                .map_err(AppError::Stdout)?;
+            if remaining.is_zero() {
+                break true;
+            }
+            tokio::select! {
+                result = self.read_char() => {
+                    result?;
+                    break false;
+                }
+                _ = ticks.tick() => (),
+            }
+        };
+        self.term.show_cursor().map_err(AppError::Stdout)?;
+        if finished {
+            self.write_line(&format!(
+                "\x07\n{}_>> Tempo de {phase} encerrado!",
+                Emoji("⏰", ":)")
+            ))
+            .await?;
+        }
+        Ok(finished)
+    }
 }
@@ -1,22 +1,174 @@
-use crate::cli::AppError;
+use crate::{
+    cli::AppError,
+    crypto::{self, Cipher},
+    lock::FileLock,
+    schema::{self, Contents},
+    tracking::{Report, TimeEntry},
+};
+use chrono::{DateTime, Duration, Local, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
-use std::{collections::BTreeMap, fmt::Display};
+use std::{
+    collections::{hash_map::DefaultHasher, BTreeMap, BTreeSet},
+    fmt::Display,
+    hash::{Hash, Hasher},
+    ops::RangeInclusive,
+};
+use tokio::io::AsyncWriteExt;
+use uuid::Uuid;
 
-#[derive(Serialize, Deserialize, Debug)]
+pub const DEFAULT_LIST: &str = "geral";
+
+pub const STORAGE_PATH: &str = "todo_storage.json";
+
+/// Arquivos auxiliares gravados ao lado do armazenamento (`<arquivo>.caldav`,
+/// `<arquivo>.sync`). Eles guardam cópias dos TODOs, então usam a mesma chave.
+const SIDE_FILES: [&str; 2] = ["caldav", "sync"];
+
+/// Tamanho mínimo do começo de um uuid aceito no lugar do id.
+const MIN_UUID_PREFIX: usize = 8;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct Todo {
+    /// Número curto mostrado ao usuário, estável enquanto o TODO existir.
     pub id: u32,
+    /// Id global, único entre cópias do arquivo editadas em paralelo.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub uuid: String,
     pub message: String,
     pub done: bool,
+    #[serde(default = "default_list")]
+    pub list: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority: Option<char>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created: Option<NaiveDate>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub completed: Option<NaiveDate>,
+    /// Data em que o TODO foi para o arquivo; TODOs arquivados não aparecem na listagem.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub archived: Option<NaiveDate>,
+    /// Data em que o TODO foi para a lixeira; ele é apagado de vez após o período de retenção.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deleted: Option<NaiveDate>,
+    /// Última alteração, usada pelo sync entre arquivos.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub modified: Option<DateTime<Utc>>,
+    /// Anotações em várias linhas; só aparecem na visão de detalhes do TODO.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub notes: String,
+    /// Campos `chave:valor` que ainda não têm lugar no modelo (ex.: `due` do todo.txt).
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub metadata: BTreeMap<String, String>,
+    /// Sessões de trabalho no TODO; a última sem fim é o cronômetro em andamento.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub time: Vec<TimeEntry>,
+    /// Pomodoros concluídos no TODO.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub pomodoros: u32,
+}
+
+impl Default for Todo {
+    fn default() -> Self {
+        Self {
+            id: 0,
+            uuid: String::new(),
+            message: String::new(),
+            done: false,
+            list: default_list(),
+            priority: None,
+            created: None,
+            completed: None,
+            archived: None,
+            deleted: None,
+            modified: None,
+            notes: String::new(),
+            metadata: BTreeMap::new(),
+            time: Vec::new(),
+            pomodoros: 0,
+        }
+    }
+}
+
+fn is_zero(count: &u32) -> bool {
+    *count == 0
+}
+
+fn default_list() -> String {
+    DEFAULT_LIST.to_string()
 }
 
 impl Todo {
     pub fn new(id: u32, message: String) -> Self {
         Self {
             id,
+            uuid: new_uuid(),
             message,
-            done: false,
+            created: Some(today()),
+            ..Default::default()
         }
     }
+
+    /// Tokens `+projeto` presentes no texto do TODO.
+    pub fn projects(&self) -> Vec<&str> {
+        self.tokens('+')
+    }
+
+    /// Tokens `@contexto` presentes no texto do TODO.
+    pub fn contexts(&self) -> Vec<&str> {
+        self.tokens('@')
+    }
+
+    /// Se o cronômetro do TODO está em andamento.
+    pub fn running(&self) -> bool {
+        self.time.last().is_some_and(TimeEntry::is_running)
+    }
+
+    /// Tempo total registrado, incluindo o cronômetro em andamento.
+    pub fn time_spent(&self) -> Duration {
+        self.time.iter().map(TimeEntry::duration).sum()
+    }
+
+    /// Se `key` identifica o TODO: o id curto ou o começo do uuid, com pelo menos
+    /// `MIN_UUID_PREFIX` caracteres.
+    pub fn matches_key(&self, key: &str) -> bool {
+        let key = key.trim().to_lowercase();
+        key == self.id.to_string()
+            || (key.len() >= MIN_UUID_PREFIX
+                && !self.uuid.is_empty()
+                && self.uuid.starts_with(&key))
+    }
+
+    /// Se o texto ou as anotações contêm `search`, sem diferenciar maiúsculas.
+    pub fn contains(&self, search: &str) -> bool {
+        let search = search.to_lowercase();
+        self.message.to_lowercase().contains(&search) || self.notes.to_lowercase().contains(&search)
+    }
+
+    fn tokens(&self, prefix: char) -> Vec<&str> {
+        self.message
+            .split_whitespace()
+            .filter_map(|word| word.strip_prefix(prefix))
+            .filter(|token| !token.is_empty())
+            .collect()
+    }
+}
+
+pub fn new_uuid() -> String {
+    Uuid::new_v4().to_string()
+}
+
+pub fn today() -> NaiveDate {
+    Local::now().date_naive()
+}
+
+/// Aceita datas ISO (`2023-02-15`), no formato brasileiro (`15/02/2023`) e no
+/// compacto do iCalendar e do Taskwarrior (`20230215` ou `20230215T103000Z`).
+pub fn parse_date(value: &str) -> Option<NaiveDate> {
+    let value = value.trim();
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .or_else(|_| NaiveDate::parse_from_str(value, "%d/%m/%Y"))
+        .ok()
+        .or_else(|| NaiveDate::parse_from_str(value.get(..8)?, "%Y%m%d").ok())
 }
 
 impl Display for Todo {
@@ -31,85 +183,622 @@ impl Display for Todo {
     }
 }
 
+#[derive(Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct ListInfo {
+    #[serde(default)]
+    pub archived: bool,
+    /// Ordem manual dos TODOs; os que não aparecem aqui vêm depois, por id.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub order: Vec<u32>,
+}
+
+/// Nova posição de um TODO na ordem manual da lista.
+#[derive(Clone, Copy)]
+pub enum Position {
+    Up,
+    Down,
+    Top,
+    Bottom,
+    /// Posição a partir de 1.
+    At(usize),
+}
+
+impl Position {
+    /// Aceita as iniciais em português (`c`ima, `b`aixo, `t`opo, `f`im), os comandos
+    /// em inglês (`up`, `down`, `top`, `bottom`) ou o número da posição.
+    pub fn parse(input: &str) -> Option<Self> {
+        match input.trim().to_lowercase().as_str() {
+            "c" | "cima" | "up" => Some(Self::Up),
+            "b" | "baixo" | "down" => Some(Self::Down),
+            "t" | "topo" | "top" => Some(Self::Top),
+            "f" | "fim" | "bottom" => Some(Self::Bottom),
+            position => position.parse().ok().filter(|n| *n > 0).map(Self::At),
+        }
+    }
+}
+
+/// Resumo de uma lista nomeada, para exibição.
+pub struct TodoList {
+    pub name: String,
+    pub archived: bool,
+    pub current: bool,
+    pub count: usize,
+}
+
+/// TODOs escolhidos para uma operação em lote.
+pub enum Selection {
+    /// Ids e intervalos, ex.: `3-7` ou `1,4,9`.
+    Ids(Vec<RangeInclusive<u32>>),
+    /// Texto procurado na mensagem e nas anotações, sem diferenciar maiúsculas.
+    Filter(String),
+}
+
+impl Selection {
+    /// Lê `3-7`, `1,4,9` ou uma combinação deles; qualquer outro texto vira um filtro.
+    pub fn parse(input: &str) -> Option<Self> {
+        let input = input.trim();
+        if input.is_empty() {
+            return None;
+        }
+        let ranges: Option<Vec<_>> = input
+            .split(',')
+            .map(|part| match part.split_once('-') {
+                Some((start, end)) => {
+                    let start: u32 = start.trim().parse().ok()?;
+                    let end = end.trim().parse().ok()?;
+                    // `7-3` vale o mesmo que `3-7`
+                    Some(start.min(end)..=start.max(end))
+                }
+                None => part.trim().parse().ok().map(|id| id..=id),
+            })
+            .collect();
+        Some(match ranges {
+            Some(ranges) => Self::Ids(ranges),
+            None => Self::Filter(input.to_string()),
+        })
+    }
+
+    pub fn matches(&self, todo: &Todo) -> bool {
+        match self {
+            Self::Ids(ranges) => ranges.iter().any(|range| range.contains(&todo.id)),
+            Self::Filter(text) => todo.contains(text) || todo.matches_key(text),
+        }
+    }
+}
+
+/// Operação aplicada de uma vez a vários TODOs.
+pub enum Bulk {
+    Done,
+    Delete,
+    /// Acrescenta um token `+projeto` ou `@contexto` ao texto.
+    Tag(String),
+    Move(String),
+    Priority(Option<char>),
+}
+
 #[async_trait::async_trait]
-pub trait TodoStorage {
+pub trait TodoStorage: Send + Sync {
     async fn add(&mut self, message: String) -> Result<Option<&Todo>, AppError>;
     async fn list(&self) -> Result<Vec<&Todo>, AppError>;
-    async fn exist(&self, id: u32) -> Result<bool, AppError>;
+    async fn find(&self, key: &str) -> Result<Option<&Todo>, AppError>;
+    /// Como `find`, mas em todas as listas, inclusive fora do arquivo e da lixeira.
+    async fn locate(&self, key: &str) -> Result<Option<&Todo>, AppError>;
     async fn update(&mut self, id: u32, message: String) -> Result<Option<&Todo>, AppError>;
+    async fn update_notes(&mut self, id: u32, notes: String) -> Result<Option<&Todo>, AppError>;
     async fn done(&mut self, id: u32) -> Result<Option<&Todo>, AppError>;
     async fn delete(&mut self, id: u32) -> Result<Option<Todo>, AppError>;
+    async fn reload(&mut self) -> Result<(), AppError>;
+    async fn merge(&mut self) -> Result<Vec<u32>, AppError>;
+    async fn refresh(&mut self) -> Result<bool, AppError>;
+    async fn import(&mut self, todos: Vec<Todo>) -> Result<usize, AppError>;
+    async fn lists(&self) -> Result<Vec<TodoList>, AppError>;
+    async fn current_list(&self) -> Result<String, AppError>;
+    async fn switch_list(&mut self, name: &str) -> Result<bool, AppError>;
+    async fn create_list(&mut self, name: &str) -> Result<bool, AppError>;
+    async fn rename_list(&mut self, name: &str, new_name: &str) -> Result<bool, AppError>;
+    async fn archive_list(&mut self, name: &str, archived: bool) -> Result<bool, AppError>;
+    async fn delete_list(&mut self, name: &str) -> Result<Option<usize>, AppError>;
+    async fn move_todo(&mut self, id: u32, list: &str) -> Result<Option<&Todo>, AppError>;
+    async fn archive_done(&mut self, min_age_days: u32) -> Result<usize, AppError>;
+    async fn archived(&self, search: &str) -> Result<Vec<&Todo>, AppError>;
+    async fn restore(&mut self, key: &str) -> Result<Option<&Todo>, AppError>;
+    async fn bulk(&mut self, ids: &[u32], operation: &Bulk) -> Result<usize, AppError>;
+    async fn trash(&self) -> Result<Vec<&Todo>, AppError>;
+    async fn untrash(&mut self, key: &str) -> Result<Option<&Todo>, AppError>;
+    async fn empty_trash(&mut self) -> Result<usize, AppError>;
+    async fn purge_trash(&mut self, retention_days: u32) -> Result<usize, AppError>;
+    async fn reorder(&mut self, id: u32, position: Position) -> Result<Option<usize>, AppError>;
+    async fn set_passphrase(&mut self, passphrase: Option<String>) -> Result<(), AppError>;
+    async fn encrypted(&self) -> Result<bool, AppError>;
+    async fn start_timer(&mut self, id: u32) -> Result<Option<&Todo>, AppError>;
+    async fn stop_timer(&mut self) -> Result<Option<&Todo>, AppError>;
+    async fn running_timer(&self) -> Result<Option<&Todo>, AppError>;
+    async fn time_report(&self, from: NaiveDate, to: NaiveDate) -> Result<Report, AppError>;
+    async fn complete_pomodoro(&mut self, id: u32) -> Result<Option<&Todo>, AppError>;
 }
 
 pub struct Todos {
     sequence: u32,
     todo_list: BTreeMap<u32, Todo>,
+    lists: BTreeMap<String, ListInfo>,
+    current: String,
     path: String,
+    base: BTreeMap<u32, Todo>, //estado do arquivo na última leitura/escrita, usado no merge
+    base_lists: BTreeMap<String, ListInfo>,
+    fingerprint: u64,
+    /// Chave do arquivo criptografado; sem ela o arquivo é gravado em texto puro.
+    cipher: Option<Cipher>,
+    /// Conteúdo gravado quando os TODOs ficam só em memória, no lugar do arquivo.
+    memory: Option<String>,
+    pretty: bool,
 }
 
 impl Todos {
-    pub async fn new() -> Result<Self, AppError> {
-        let path: String = "todo_storage.json".to_string();
-        let contents = tokio::fs::read_to_string(&path)
-            .await
-            .map_err(AppError::Read)?;
-        let (sequence, todo_list) =
-            serde_json::from_str(&contents).unwrap_or((0_u32, BTreeMap::<u32, Todo>::new()));
+    pub async fn open(path: &str) -> Result<Self, AppError> {
+        Self::open_with(path, None).await
+    }
+
+    /// Abre um arquivo criptografado com a senha; um arquivo em texto puro é aberto
+    /// normalmente e continua sem criptografia.
+    pub async fn unlock(path: &str, passphrase: &str) -> Result<Self, AppError> {
+        let contents = {
+            let _lock = FileLock::acquire(path)?;
+            Self::read(path).await?
+        };
+        let cipher = if crypto::is_encrypted(&contents) {
+            Some(Cipher::unlock(passphrase, &contents).await?)
+        } else {
+            None
+        };
+        Self::open_with(path, cipher).await
+    }
+
+    /// Se o arquivo está criptografado e precisa de senha para ser aberto.
+    pub async fn is_encrypted(path: &str) -> Result<bool, AppError> {
+        Ok(crypto::is_encrypted(&Self::read(path).await?))
+    }
+
+    async fn open_with(path: &str, cipher: Option<Cipher>) -> Result<Self, AppError> {
+        let path = path.to_string();
+        let contents = {
+            let _lock = FileLock::acquire(&path)?;
+            Self::read(&path).await?
+        };
+        let (sequence, todo_list, lists) = Self::parse(&Self::decrypt(&cipher, &contents)?)?;
 
         Ok(Self {
             sequence,
+            base: todo_list.clone(),
             todo_list,
+            base_lists: lists.clone(),
+            lists,
+            current: default_list(),
             path,
+            fingerprint: fingerprint(&contents),
+            cipher,
+            memory: None,
+            pretty: false,
         })
     }
 
+    /// TODOs só em memória, sem arquivo nem trava, para testar o `TodoCli` sem tocar
+    /// no disco.
+    pub fn in_memory() -> Self {
+        Self {
+            sequence: 0,
+            todo_list: BTreeMap::new(),
+            lists: BTreeMap::from([(default_list(), ListInfo::default())]),
+            current: default_list(),
+            path: String::new(),
+            base: BTreeMap::new(),
+            base_lists: BTreeMap::new(),
+            fingerprint: fingerprint(""),
+            cipher: None,
+            memory: Some(String::new()),
+            pretty: false,
+        }
+    }
+
+    /// Grava o JSON indentado, um campo por linha, para que um diff (ex.: no
+    /// armazenamento git) mostre só os campos alterados.
+    pub fn pretty(mut self) -> Self {
+        self.pretty = true;
+        self
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Todos os TODOs, de todas as listas.
+    pub fn all(&self) -> Vec<&Todo> {
+        self.todo_list.values().collect()
+    }
+
+    /// Se o TODO aparece na listagem: está na lista atual, fora do arquivo e da lixeira.
+    fn is_listed(&self, todo: &Todo) -> bool {
+        todo.list == self.current && todo.archived.is_none() && todo.deleted.is_none()
+    }
+
+    fn in_current_list(&self, id: u32) -> Option<&Todo> {
+        self.todo_list.get(&id).filter(|todo| self.is_listed(todo))
+    }
+
+    pub fn get(&self, id: u32) -> Option<&Todo> {
+        self.todo_list.get(&id)
+    }
+
+    /// Id curto do TODO identificado por `key` (id ou começo do uuid).
+    fn lookup(&self, key: &str) -> Option<u32> {
+        self.todo_list
+            .values()
+            .find(|todo| todo.matches_key(key))
+            .map(|todo| todo.id)
+    }
+
+    /// Insere ou substitui TODOs numa única gravação. O TODO existente é achado pelo
+    /// uuid ou, se o uuid não vier, pelo id; os demais recebem um novo id e entram na
+    /// lista atual. Os ids finais são devolvidos na mesma ordem.
+    pub async fn upsert(&mut self, todos: Vec<Todo>) -> Result<Vec<u32>, AppError> {
+        let mut ids = Vec::new();
+        for mut todo in todos {
+            let by_uuid = self
+                .todo_list
+                .values()
+                .find(|other| !todo.uuid.is_empty() && other.uuid == todo.uuid);
+            let existing = match by_uuid {
+                Some(other) => Some(other),
+                None => self
+                    .todo_list
+                    .get(&todo.id)
+                    .filter(|other| todo.uuid.is_empty() || other.uuid == todo.uuid),
+            };
+            match existing {
+                Some(other) => {
+                    todo.id = other.id;
+                    if todo.uuid.is_empty() {
+                        todo.uuid = other.uuid.clone();
+                    }
+                }
+                None => {
+                    todo.id = self.next_id();
+                    todo.list = self.current.clone();
+                    if todo.uuid.is_empty() {
+                        todo.uuid = new_uuid();
+                    }
+                }
+            }
+            ids.push(todo.id);
+            self.todo_list.insert(todo.id, todo);
+        }
+        self.save().await?;
+        Ok(ids)
+    }
+
     fn next_id(&mut self) -> u32 {
         self.sequence += 1;
         self.sequence
     }
 
-    async fn save(&self) -> Result<(), AppError> {
-        let contents =
-            serde_json::to_string(&(self.sequence, &self.todo_list)).map_err(AppError::Parse)?;
-        tokio::fs::write(&self.path, contents)
-            .await
-            .map_err(AppError::Write)?;
+    /// Trava o arquivo e lê o conteúdo atual; em memória não há o que travar.
+    async fn read_locked(&self) -> Result<(Option<FileLock>, String), AppError> {
+        match &self.memory {
+            Some(contents) => Ok((None, contents.clone())),
+            None => {
+                let lock = FileLock::acquire(&self.path)?;
+                Ok((Some(lock), Self::read(&self.path).await?))
+            }
+        }
+    }
+
+    /// Um arquivo que ainda não existe é lido como vazio.
+    async fn read(path: &str) -> Result<String, AppError> {
+        match tokio::fs::read_to_string(path).await {
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(String::new()),
+            result => result.map_err(AppError::Read),
+        }
+    }
+
+    /// Conteúdo em texto puro do arquivo, decifrado quando ele está criptografado.
+    fn decrypt(cipher: &Option<Cipher>, contents: &str) -> Result<String, AppError> {
+        match cipher {
+            Some(cipher) if crypto::is_encrypted(contents) => cipher.decrypt(contents),
+            None if crypto::is_encrypted(contents) => Err(AppError::WrongPassphrase),
+            _ => Ok(contents.to_string()),
+        }
+    }
+
+    /// Lê o arquivo em qualquer versão do formato, garantindo que toda lista usada
+    /// pelos TODOs exista.
+    fn parse(contents: &str) -> Result<Contents, AppError> {
+        let (sequence, todo_list, mut lists) = schema::read(contents)?;
+        lists.entry(default_list()).or_default();
+        for todo in todo_list.values() {
+            lists.entry(todo.list.clone()).or_default();
+        }
+        Ok((sequence, todo_list, lists))
+    }
+
+    fn load(&mut self, contents: &str) -> Result<(), AppError> {
+        let (sequence, todo_list, lists) = Self::parse(&Self::decrypt(&self.cipher, contents)?)?;
+        self.sequence = sequence;
+        self.base = todo_list.clone();
+        self.todo_list = todo_list;
+        self.base_lists = lists.clone();
+        self.lists = lists;
+        if !self.lists.contains_key(&self.current) {
+            self.current = default_list();
+        }
+        self.fingerprint = fingerprint(contents);
         Ok(())
     }
+
+    /// Salva a lista, recusando sobrescrever alterações feitas por outro processo
+    /// desde a última leitura/escrita.
+    async fn save(&mut self) -> Result<(), AppError> {
+        let (_lock, current) = self.read_locked().await?;
+        if fingerprint(&current) != self.fingerprint {
+            return Err(AppError::Conflict);
+        }
+        self.stamp();
+        self.write().await
+    }
+
+    /// Carimba os TODOs alterados desde a base; quem já trouxe a própria data (ex.: o
+    /// sync ou o outro processo de um merge) a mantém.
+    fn stamp(&mut self) {
+        let now = Utc::now();
+        for todo in self.todo_list.values_mut() {
+            let base = self.base.get(&todo.id);
+            if base != Some(todo) && base.and_then(|base| base.modified) == todo.modified {
+                todo.modified = Some(now);
+            }
+        }
+    }
+
+    /// Lê o arquivo auxiliar `<arquivo>.<extension>`, decifrado com a chave do
+    /// armazenamento; `None` se ele não existe ou se os TODOs estão só em memória.
+    pub async fn read_side(&self, extension: &str) -> Result<Option<String>, AppError> {
+        if self.memory.is_some() {
+            return Ok(None);
+        }
+        match tokio::fs::read_to_string(format!("{}.{extension}", self.path)).await {
+            Ok(contents) => Self::decrypt(&self.cipher, &contents).map(Some),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(AppError::Read(err)),
+        }
+    }
+
+    /// Grava o arquivo auxiliar `<arquivo>.<extension>`, criptografado quando o
+    /// armazenamento está.
+    pub async fn write_side(&self, extension: &str, contents: &str) -> Result<(), AppError> {
+        if self.memory.is_some() {
+            return Ok(());
+        }
+        let contents = match &self.cipher {
+            Some(cipher) => cipher.encrypt(contents)?,
+            None => contents.to_string(),
+        };
+        write_file(&format!("{}.{extension}", self.path), &contents).await
+    }
+
+    pub fn sequence(&self) -> u32 {
+        self.sequence
+    }
+
+    pub fn lists_info(&self) -> &BTreeMap<String, ListInfo> {
+        &self.lists
+    }
+
+    /// Substitui todo o conteúdo e salva, com a mesma checagem de conflito das
+    /// demais alterações.
+    pub async fn replace(
+        &mut self,
+        sequence: u32,
+        todo_list: BTreeMap<u32, Todo>,
+        lists: BTreeMap<String, ListInfo>,
+    ) -> Result<(), AppError> {
+        self.sequence = sequence;
+        self.todo_list = todo_list;
+        self.lists = lists;
+        if !self.lists.contains_key(&self.current) {
+            self.current = default_list();
+        }
+        self.save().await
+    }
+
+    async fn write(&mut self) -> Result<(), AppError> {
+        let mut contents = schema::write(self.sequence, &self.todo_list, &self.lists, self.pretty)?;
+        if let Some(cipher) = &self.cipher {
+            contents = cipher.encrypt(&contents)?;
+        }
+        match &mut self.memory {
+            Some(memory) => memory.clone_from(&contents),
+            None => write_file(&self.path, &contents).await?,
+        }
+        self.fingerprint = fingerprint(&contents);
+        self.base = self.todo_list.clone();
+        self.base_lists = self.lists.clone();
+        Ok(())
+    }
+
+    /// Merge de três vias entre o estado base, a lista em memória e a lista do disco.
+    /// Quando os dois lados alteraram o mesmo TODO, a versão local é mantida e o id é
+    /// devolvido como conflito. TODOs diferentes (uuids distintos) criados com o mesmo
+    /// id nos dois lados: o local recebe um novo id para não se perder.
+    fn merge_with(&mut self, sequence: u32, remote: BTreeMap<u32, Todo>) -> Vec<u32> {
+        let ids: BTreeSet<u32> = self
+            .base
+            .keys()
+            .chain(self.todo_list.keys())
+            .chain(remote.keys())
+            .copied()
+            .collect();
+        let mut merged = BTreeMap::new();
+        let mut conflicts = Vec::new();
+        let mut renumbered = Vec::new();
+
+        for id in ids {
+            let base = self.base.get(&id);
+            let local = self.todo_list.get(&id);
+            let theirs = remote.get(&id);
+            // o mesmo id curto criado nos dois lados para TODOs diferentes
+            let added_twice = base.is_none()
+                && matches!((local, theirs), (Some(local), Some(theirs)) if local.uuid != theirs.uuid);
+            let chosen = if local == base {
+                theirs
+            } else if theirs == base || theirs == local {
+                local
+            } else if added_twice {
+                renumbered.extend(local.cloned());
+                theirs
+            } else {
+                conflicts.push(id);
+                local
+            };
+            if let Some(todo) = chosen {
+                merged.insert(id, todo.clone());
+            }
+        }
+
+        self.sequence = self.sequence.max(sequence);
+        for mut todo in renumbered {
+            todo.id = self.next_id();
+            merged.insert(todo.id, todo);
+        }
+        self.todo_list = merged;
+        conflicts
+    }
+
+    /// Merge de três vias das listas nomeadas. Uma lista alterada dos dois lados tem
+    /// os campos juntados: cada um fica com o valor do lado que o mudou (o local, se
+    /// os dois mudaram o mesmo campo).
+    fn merge_lists(&mut self, remote: BTreeMap<String, ListInfo>) {
+        let names: BTreeSet<String> = self
+            .base_lists
+            .keys()
+            .chain(self.lists.keys())
+            .chain(remote.keys())
+            .cloned()
+            .collect();
+        let mut merged = BTreeMap::new();
+        for name in names {
+            let base = self.base_lists.get(&name);
+            let local = self.lists.get(&name);
+            let theirs = remote.get(&name);
+            let chosen = if local == base {
+                theirs.cloned()
+            } else if theirs == base || theirs == local {
+                local.cloned()
+            } else {
+                match (local, theirs) {
+                    (Some(local), Some(theirs)) => {
+                        let base = base.cloned().unwrap_or_default();
+                        let mut info = local.clone();
+                        if local.archived == base.archived {
+                            info.archived = theirs.archived;
+                        }
+                        if local.order == base.order {
+                            info.order.clone_from(&theirs.order);
+                        }
+                        Some(info)
+                    }
+                    // apagada de um lado e alterada do outro: a alteração é mantida
+                    (local, theirs) => local.or(theirs).cloned(),
+                }
+            };
+            if let Some(info) = chosen {
+                merged.insert(name, info);
+            }
+        }
+        self.lists = merged;
+        if !self.lists.contains_key(&self.current) {
+            self.current = default_list();
+        }
+    }
+}
+
+fn fingerprint(contents: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    contents.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Grava num `<path>.tmp` e renomeia por cima do arquivo: uma queda no meio da
+/// escrita deixa o arquivo anterior intacto em vez de um arquivo cortado.
+pub(crate) async fn write_file(path: &str, contents: &str) -> Result<(), AppError> {
+    let temp = format!("{path}.tmp");
+    let mut file = tokio::fs::File::create(&temp)
+        .await
+        .map_err(AppError::Write)?;
+    file.write_all(contents.as_bytes())
+        .await
+        .map_err(AppError::Write)?;
+    file.sync_all().await.map_err(AppError::Write)?;
+    tokio::fs::rename(&temp, path)
+        .await
+        .map_err(AppError::Write)
 }
 
 #[async_trait::async_trait]
 impl TodoStorage for Todos {
     async fn add(&mut self, message: String) -> Result<Option<&Todo>, AppError> {
         let id = self.next_id();
-        let todo = Todo::new(id, message);
+        let mut todo = Todo::new(id, message);
+        todo.list = self.current.clone();
         self.todo_list.insert(id, todo);
         self.save().await?;
         Ok(self.todo_list.get(&id))
     }
 
     async fn list(&self) -> Result<Vec<&Todo>, AppError> {
-        Ok(self.todo_list.values().collect())
+        let order = self
+            .lists
+            .get(&self.current)
+            .map(|info| info.order.as_slice())
+            .unwrap_or_default();
+        let mut list: Vec<&Todo> = self
+            .todo_list
+            .values()
+            .filter(|todo| self.is_listed(todo))
+            .collect();
+        // sort estável: os que estão fora da ordem manual continuam em ordem de id
+        list.sort_by_key(|todo| {
+            order
+                .iter()
+                .position(|id| *id == todo.id)
+                .unwrap_or(usize::MAX)
+        });
+        Ok(list)
     }
 
-    async fn exist(&self, id: u32) -> Result<bool, AppError> {
-        Ok(self.todo_list.contains_key(&id))
+    async fn find(&self, key: &str) -> Result<Option<&Todo>, AppError> {
+        Ok(self.lookup(key).and_then(|id| self.in_current_list(id)))
+    }
+
+    async fn locate(&self, key: &str) -> Result<Option<&Todo>, AppError> {
+        Ok(self.lookup(key).and_then(|id| self.get(id)))
     }
 
     async fn done(&mut self, id: u32) -> Result<Option<&Todo>, AppError> {
         if let Some(todo) = self.todo_list.get_mut(&id) {
             todo.done = true;
+            todo.completed = Some(today());
             self.save().await?;
         }
         Ok(self.todo_list.get(&id)) //realizando uma nova busca para retornar o todo sem a mutabilidade
     }
 
+    /// Move o TODO para a lixeira; ele só é apagado de vez ao esvaziar a lixeira.
     async fn delete(&mut self, id: u32) -> Result<Option<Todo>, AppError> {
-        if let Some(todo) = self.todo_list.remove(&id) {
-            self.save().await?;
-            Ok(Some(todo))
-        } else {
-            Ok(None)
+        match self.todo_list.get_mut(&id) {
+            Some(todo) if todo.deleted.is_none() => {
+                todo.deleted = Some(today());
+                let todo = todo.clone();
+                self.save().await?;
+                Ok(Some(todo))
+            }
+            _ => Ok(None),
         }
     }
 
@@ -122,4 +811,405 @@ impl TodoStorage for Todos {
         //Ok(None)
         Ok(self.todo_list.get(&id)) //realizando uma nova busca para retornar o todo sem a mutabilidade
     }
+
+    async fn update_notes(&mut self, id: u32, notes: String) -> Result<Option<&Todo>, AppError> {
+        if let Some(todo) = self.todo_list.get_mut(&id) {
+            todo.notes = notes;
+            self.save().await?;
+        }
+        Ok(self.todo_list.get(&id))
+    }
+
+    async fn reload(&mut self) -> Result<(), AppError> {
+        let (_lock, contents) = self.read_locked().await?;
+        self.load(&contents)
+    }
+
+    async fn merge(&mut self) -> Result<Vec<u32>, AppError> {
+        let (_lock, contents) = self.read_locked().await?;
+        let (sequence, remote, lists) = Self::parse(&Self::decrypt(&self.cipher, &contents)?)?;
+        let conflicts = self.merge_with(sequence, remote);
+        self.merge_lists(lists);
+        self.stamp();
+        self.write().await?;
+        Ok(conflicts)
+    }
+
+    async fn refresh(&mut self) -> Result<bool, AppError> {
+        let (_lock, contents) = self.read_locked().await?;
+        if fingerprint(&contents) == self.fingerprint {
+            return Ok(false);
+        }
+        self.load(&contents)?;
+        Ok(true)
+    }
+
+    async fn import(&mut self, todos: Vec<Todo>) -> Result<usize, AppError> {
+        Ok(self.upsert(todos).await?.len())
+    }
+
+    async fn lists(&self) -> Result<Vec<TodoList>, AppError> {
+        Ok(self
+            .lists
+            .iter()
+            .map(|(name, info)| TodoList {
+                name: name.clone(),
+                archived: info.archived,
+                current: *name == self.current,
+                count: self
+                    .todo_list
+                    .values()
+                    .filter(|todo| todo.list == *name)
+                    .count(),
+            })
+            .collect())
+    }
+
+    async fn current_list(&self) -> Result<String, AppError> {
+        Ok(self.current.clone())
+    }
+
+    async fn switch_list(&mut self, name: &str) -> Result<bool, AppError> {
+        match self.lists.get(name) {
+            Some(info) if !info.archived => {
+                self.current = name.to_string();
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    async fn create_list(&mut self, name: &str) -> Result<bool, AppError> {
+        if name.is_empty() || self.lists.contains_key(name) {
+            return Ok(false);
+        }
+        self.lists.insert(name.to_string(), ListInfo::default());
+        self.save().await?;
+        Ok(true)
+    }
+
+    async fn rename_list(&mut self, name: &str, new_name: &str) -> Result<bool, AppError> {
+        if name == DEFAULT_LIST || new_name.is_empty() || self.lists.contains_key(new_name) {
+            return Ok(false);
+        }
+        let Some(info) = self.lists.remove(name) else {
+            return Ok(false);
+        };
+        self.lists.insert(new_name.to_string(), info);
+        for todo in self.todo_list.values_mut().filter(|todo| todo.list == name) {
+            todo.list = new_name.to_string();
+        }
+        if self.current == name {
+            self.current = new_name.to_string();
+        }
+        self.save().await?;
+        Ok(true)
+    }
+
+    async fn archive_list(&mut self, name: &str, archived: bool) -> Result<bool, AppError> {
+        if name == DEFAULT_LIST {
+            return Ok(false);
+        }
+        let Some(info) = self.lists.get_mut(name) else {
+            return Ok(false);
+        };
+        info.archived = archived;
+        if archived && self.current == name {
+            self.current = default_list();
+        }
+        self.save().await?;
+        Ok(true)
+    }
+
+    /// Apaga a lista e manda os TODOs dela para a lixeira da lista padrão, de onde
+    /// podem ser restaurados. Devolve quantos TODOs foram para a lixeira.
+    async fn delete_list(&mut self, name: &str) -> Result<Option<usize>, AppError> {
+        if name == DEFAULT_LIST || self.lists.remove(name).is_none() {
+            return Ok(None);
+        }
+        let mut count = 0;
+        for todo in self.todo_list.values_mut().filter(|todo| todo.list == name) {
+            todo.list = default_list();
+            if todo.deleted.is_none() {
+                todo.deleted = Some(today());
+                count += 1;
+            }
+        }
+        if self.current == name {
+            self.current = default_list();
+        }
+        self.save().await?;
+        Ok(Some(count))
+    }
+
+    async fn move_todo(&mut self, id: u32, list: &str) -> Result<Option<&Todo>, AppError> {
+        let open = self.lists.get(list).is_some_and(|info| !info.archived);
+        match self.todo_list.get_mut(&id) {
+            Some(todo) if open => {
+                todo.list = list.to_string();
+                self.save().await?;
+                Ok(self.todo_list.get(&id))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Arquiva os TODOs feitos há pelo menos `min_age_days` dias (0 arquiva todos os
+    /// feitos). TODOs feitos sem data de conclusão são considerados antigos.
+    async fn archive_done(&mut self, min_age_days: u32) -> Result<usize, AppError> {
+        let today = today();
+        let limit = today - chrono::Duration::days(min_age_days.into());
+        let mut count = 0;
+        for todo in self.todo_list.values_mut() {
+            let old_enough = todo.completed.is_none_or(|completed| completed <= limit);
+            if todo.done && todo.archived.is_none() && todo.deleted.is_none() && old_enough {
+                todo.archived = Some(today);
+                count += 1;
+            }
+        }
+        if count > 0 {
+            self.save().await?;
+        }
+        Ok(count)
+    }
+
+    async fn archived(&self, search: &str) -> Result<Vec<&Todo>, AppError> {
+        Ok(self
+            .todo_list
+            .values()
+            .filter(|todo| todo.list == self.current && todo.deleted.is_none())
+            .filter(|todo| todo.archived.is_some() && todo.contains(search))
+            .collect())
+    }
+
+    async fn restore(&mut self, key: &str) -> Result<Option<&Todo>, AppError> {
+        let Some(id) = self.lookup(key) else {
+            return Ok(None);
+        };
+        match self.todo_list.get_mut(&id) {
+            Some(todo) if todo.archived.is_some() && todo.deleted.is_none() => {
+                todo.archived = None;
+                self.save().await?;
+                Ok(self.todo_list.get(&id))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Aplica a operação a todos os TODOs da lista atual informados, com uma única
+    /// gravação. Devolve quantos TODOs foram alterados.
+    async fn bulk(&mut self, ids: &[u32], operation: &Bulk) -> Result<usize, AppError> {
+        if let Bulk::Move(list) = operation {
+            if self.lists.get(list).is_none_or(|info| info.archived) {
+                return Ok(0);
+            }
+        }
+        let ids: Vec<u32> = ids
+            .iter()
+            .copied()
+            .filter(|id| self.in_current_list(*id).is_some())
+            .collect();
+        for id in &ids {
+            let Some(todo) = self.todo_list.get_mut(id) else {
+                continue;
+            };
+            match operation {
+                Bulk::Done => {
+                    if !todo.done {
+                        todo.done = true;
+                        todo.completed = Some(today());
+                    }
+                }
+                Bulk::Tag(tag) => {
+                    if !todo.message.split_whitespace().any(|word| word == tag) {
+                        todo.message = format!("{} {tag}", todo.message);
+                    }
+                }
+                Bulk::Move(list) => todo.list = list.clone(),
+                Bulk::Priority(priority) => todo.priority = *priority,
+                Bulk::Delete => todo.deleted = Some(today()),
+            }
+        }
+        if !ids.is_empty() {
+            self.save().await?;
+        }
+        Ok(ids.len())
+    }
+
+    async fn trash(&self) -> Result<Vec<&Todo>, AppError> {
+        Ok(self
+            .todo_list
+            .values()
+            .filter(|todo| todo.list == self.current && todo.deleted.is_some())
+            .collect())
+    }
+
+    async fn untrash(&mut self, key: &str) -> Result<Option<&Todo>, AppError> {
+        let Some(id) = self.lookup(key) else {
+            return Ok(None);
+        };
+        match self.todo_list.get_mut(&id) {
+            Some(todo) if todo.deleted.is_some() => {
+                todo.deleted = None;
+                self.save().await?;
+                Ok(self.todo_list.get(&id))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Apaga de vez os TODOs da lixeira da lista atual.
+    async fn empty_trash(&mut self) -> Result<usize, AppError> {
+        let count = self.todo_list.len();
+        let current = &self.current;
+        self.todo_list
+            .retain(|_, todo| todo.list != *current || todo.deleted.is_none());
+        let count = count - self.todo_list.len();
+        if count > 0 {
+            self.save().await?;
+        }
+        Ok(count)
+    }
+
+    /// Apaga de vez, em todas as listas, os TODOs que estão na lixeira há mais de
+    /// `retention_days` dias.
+    async fn purge_trash(&mut self, retention_days: u32) -> Result<usize, AppError> {
+        let limit = today() - chrono::Duration::days(retention_days.into());
+        let count = self.todo_list.len();
+        self.todo_list
+            .retain(|_, todo| todo.deleted.is_none_or(|deleted| deleted > limit));
+        let count = count - self.todo_list.len();
+        if count > 0 {
+            self.save().await?;
+        }
+        Ok(count)
+    }
+
+    /// Move o TODO na ordem manual da lista atual e devolve a nova posição (a partir de 1).
+    /// A posição conta só os TODOs listados; os que estão fora do arquivo ou na
+    /// lixeira mantêm o seu lugar na ordem para quando voltarem.
+    async fn reorder(&mut self, id: u32, position: Position) -> Result<Option<usize>, AppError> {
+        let mut visible: Vec<u32> = self.list().await?.iter().map(|todo| todo.id).collect();
+        let Some(index) = visible.iter().position(|other| *other == id) else {
+            return Ok(None);
+        };
+        visible.remove(index);
+        let target = match position {
+            Position::Up => index.saturating_sub(1),
+            Position::Down => index + 1,
+            Position::Top => 0,
+            Position::Bottom => visible.len(),
+            Position::At(position) => position - 1,
+        }
+        .min(visible.len());
+        let Some(info) = self.lists.get_mut(&self.current) else {
+            return Ok(None);
+        };
+        // os listados fora da ordem manual entram no fim, na ordem em que aparecem
+        let missing: Vec<u32> = visible
+            .iter()
+            .copied()
+            .filter(|other| !info.order.contains(other))
+            .collect();
+        info.order.extend(missing);
+        info.order.retain(|other| *other != id);
+        let at = match visible.get(target) {
+            Some(next) => info.order.iter().position(|other| other == next),
+            None => visible
+                .last()
+                .and_then(|last| info.order.iter().position(|other| other == last))
+                .map(|last| last + 1),
+        }
+        .unwrap_or(info.order.len());
+        info.order.insert(at, id);
+        self.save().await?;
+        Ok(Some(target + 1))
+    }
+
+    /// Criptografa o arquivo com uma nova senha ou, com `None`, volta a gravá-lo em
+    /// texto puro.
+    async fn set_passphrase(&mut self, passphrase: Option<String>) -> Result<(), AppError> {
+        let cipher = match passphrase {
+            Some(passphrase) => Some(Cipher::new(&passphrase).await?),
+            None => None,
+        };
+        let mut sides = Vec::new();
+        for extension in SIDE_FILES {
+            if let Some(contents) = self.read_side(extension).await? {
+                sides.push((extension, contents));
+            }
+        }
+        let previous = std::mem::replace(&mut self.cipher, cipher);
+        // sem gravar, o arquivo no disco continua com a senha antiga
+        if let Err(err) = self.save().await {
+            self.cipher = previous;
+            return Err(err);
+        }
+        for (extension, contents) in sides {
+            self.write_side(extension, &contents).await?;
+        }
+        Ok(())
+    }
+
+    async fn encrypted(&self) -> Result<bool, AppError> {
+        Ok(self.cipher.is_some())
+    }
+
+    /// Inicia o cronômetro do TODO, parando o que estiver em andamento em outro.
+    async fn start_timer(&mut self, id: u32) -> Result<Option<&Todo>, AppError> {
+        if !self.todo_list.contains_key(&id) {
+            return Ok(None);
+        }
+        let now = Utc::now();
+        let mut changed = false;
+        for todo in self.todo_list.values_mut() {
+            if todo.id != id {
+                if let Some(entry) = todo.time.last_mut().filter(|entry| entry.is_running()) {
+                    entry.end = Some(now);
+                    changed = true;
+                }
+            } else if !todo.running() {
+                todo.time.push(TimeEntry::start(now));
+                changed = true;
+            }
+        }
+        if changed {
+            self.save().await?;
+        }
+        Ok(self.todo_list.get(&id))
+    }
+
+    /// Para o cronômetro em andamento e devolve o TODO dele.
+    async fn stop_timer(&mut self) -> Result<Option<&Todo>, AppError> {
+        let Some(id) = self.running_timer().await?.map(|todo| todo.id) else {
+            return Ok(None);
+        };
+        if let Some(entry) = self
+            .todo_list
+            .get_mut(&id)
+            .and_then(|todo| todo.time.last_mut())
+        {
+            entry.end = Some(Utc::now());
+        }
+        self.save().await?;
+        Ok(self.todo_list.get(&id))
+    }
+
+    async fn running_timer(&self) -> Result<Option<&Todo>, AppError> {
+        Ok(self.todo_list.values().find(|todo| todo.running()))
+    }
+
+    /// Tempo registrado entre as duas datas em todos os TODOs, inclusive os
+    /// arquivados e os da lixeira.
+    async fn time_report(&self, from: NaiveDate, to: NaiveDate) -> Result<Report, AppError> {
+        Ok(Report::new(&self.all(), from, to))
+    }
+
+    async fn complete_pomodoro(&mut self, id: u32) -> Result<Option<&Todo>, AppError> {
+        if let Some(todo) = self.todo_list.get_mut(&id) {
+            todo.pomodoros += 1;
+            self.save().await?;
+        }
+        Ok(self.todo_list.get(&id))
+    }
 }
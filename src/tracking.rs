@@ -0,0 +1,131 @@
+//! Controle de horas: entradas de tempo dos TODOs e o relatório por TODO, tag e dia.
+//!
+//! Cada entrada guarda o início e o fim em UTC; a entrada sem fim é o cronômetro em
+//! andamento, e só um TODO pode ter um. No relatório as entradas são cortadas na
+//! meia-noite local, para que uma sessão que vira o dia conte em cada um deles.
+
+use crate::todo::Todo;
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Tag dos TODOs que não têm `+projeto` nem `@contexto` no texto.
+pub const UNTAGGED: &str = "(sem tag)";
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TimeEntry {
+    pub start: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end: Option<DateTime<Utc>>,
+}
+
+impl TimeEntry {
+    pub fn start(now: DateTime<Utc>) -> Self {
+        Self {
+            start: now,
+            end: None,
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.end.is_none()
+    }
+
+    /// Duração até o fim ou, com o cronômetro em andamento, até agora.
+    pub fn duration(&self) -> Duration {
+        self.end.unwrap_or_else(Utc::now) - self.start
+    }
+
+    /// Partes da entrada em cada dia local, em horário local.
+    fn days(&self) -> Vec<(NaiveDate, Duration)> {
+        let end = local(self.end.unwrap_or_else(Utc::now));
+        let mut cursor = local(self.start);
+        let mut days = Vec::new();
+        while cursor < end {
+            let midnight = (cursor.date() + Duration::days(1))
+                .and_hms_opt(0, 0, 0)
+                .expect("meia-noite é uma hora válida");
+            let until = midnight.min(end);
+            days.push((cursor.date(), until - cursor));
+            cursor = until;
+        }
+        days
+    }
+}
+
+fn local(time: DateTime<Utc>) -> NaiveDateTime {
+    time.with_timezone(&Local).naive_local()
+}
+
+/// Tempo registrado num período, somado de três formas.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Report {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    /// `(id, texto, tempo)` dos TODOs com tempo no período, do maior para o menor.
+    pub todos: Vec<(u32, String, Duration)>,
+    pub tags: BTreeMap<String, Duration>,
+    pub days: BTreeMap<NaiveDate, Duration>,
+    pub total: Duration,
+}
+
+impl Report {
+    /// Soma o tempo registrado entre `from` e `to`, inclusive.
+    pub fn new(todos: &[&Todo], from: NaiveDate, to: NaiveDate) -> Self {
+        let mut report = Self {
+            from,
+            to,
+            todos: Vec::new(),
+            tags: BTreeMap::new(),
+            days: BTreeMap::new(),
+            total: Duration::zero(),
+        };
+        for todo in todos {
+            let mut spent = Duration::zero();
+            for (day, duration) in todo.time.iter().flat_map(TimeEntry::days) {
+                if (from..=to).contains(&day) {
+                    *report.days.entry(day).or_insert_with(Duration::zero) += duration;
+                    spent += duration;
+                }
+            }
+            if spent.is_zero() {
+                continue;
+            }
+            let mut tags: Vec<String> = todo
+                .projects()
+                .into_iter()
+                .map(|project| format!("+{project}"))
+                .chain(
+                    todo.contexts()
+                        .into_iter()
+                        .map(|context| format!("@{context}")),
+                )
+                .collect();
+            if tags.is_empty() {
+                tags.push(UNTAGGED.to_string());
+            }
+            // um TODO com duas tags conta inteiro nas duas
+            for tag in tags {
+                *report.tags.entry(tag).or_insert_with(Duration::zero) += spent;
+            }
+            report.todos.push((todo.id, todo.message.clone(), spent));
+            report.total += spent;
+        }
+        report
+            .todos
+            .sort_by(|(a_id, _, a), (b_id, _, b)| b.cmp(a).then(a_id.cmp(b_id)));
+        report
+    }
+}
+
+/// Período padrão do relatório: do primeiro dia do mês até hoje.
+pub fn this_month() -> (NaiveDate, NaiveDate) {
+    let today = Local::now().date_naive();
+    (today.with_day(1).unwrap_or(today), today)
+}
+
+/// `1h05min`, arredondado para baixo no minuto.
+pub fn format_duration(duration: Duration) -> String {
+    let minutes = duration.num_minutes().max(0);
+    format!("{}h{:02}min", minutes / 60, minutes % 60)
+}
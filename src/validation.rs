@@ -0,0 +1,89 @@
+//! Regras aplicadas ao texto de um TODO antes de ele chegar ao armazenamento.
+
+use crate::{cli::AppError, todo::Todo};
+use std::{env, fmt::Display};
+
+/// Tamanho máximo do texto quando `TODO_MAX_LENGTH` não está definida.
+const MAX_LENGTH: usize = 200;
+
+pub enum ValidationError {
+    Empty,
+    TooLong { length: usize, max: usize },
+}
+
+impl Display for ValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Empty => write!(f, "o texto do TODO está vazio"),
+            Self::TooLong { length, max } => write!(
+                f,
+                "o texto do TODO tem {length} caracteres, o máximo é {max}"
+            ),
+        }
+    }
+}
+
+/// Texto já normalizado e, se houver, o id de um TODO aberto com o mesmo texto.
+pub struct Message {
+    pub text: String,
+    pub duplicate: Option<u32>,
+}
+
+pub struct Validator {
+    max_length: usize,
+}
+
+impl Default for Validator {
+    fn default() -> Self {
+        Self {
+            max_length: MAX_LENGTH,
+        }
+    }
+}
+
+impl Validator {
+    pub fn from_env() -> Self {
+        let max_length = env::var("TODO_MAX_LENGTH")
+            .ok()
+            .and_then(|max| max.parse().ok())
+            .unwrap_or(MAX_LENGTH);
+        Self { max_length }
+    }
+
+    /// Normaliza o texto e confere as regras. `id` é o TODO que está sendo editado,
+    /// que não conta como duplicado de si mesmo.
+    pub fn message(
+        &self,
+        text: &str,
+        id: Option<u32>,
+        open: &[&Todo],
+    ) -> Result<Message, AppError> {
+        let text = normalize(text);
+        if text.is_empty() {
+            return Err(AppError::Validation(ValidationError::Empty));
+        }
+        let length = text.chars().count();
+        if length > self.max_length {
+            return Err(AppError::Validation(ValidationError::TooLong {
+                length,
+                max: self.max_length,
+            }));
+        }
+        let duplicate = open
+            .iter()
+            .find(|todo| !todo.done && Some(todo.id) != id && todo.message == text)
+            .map(|todo| todo.id);
+        Ok(Message { text, duplicate })
+    }
+}
+
+/// Troca caracteres de controle (tabs, quebras de linha...) por espaços e junta os
+/// espaços repetidos.
+fn normalize(text: &str) -> String {
+    text.chars()
+        .map(|char| if char.is_control() { ' ' } else { char })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
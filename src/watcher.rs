@@ -0,0 +1,64 @@
+use crate::cli::AppError;
+use std::time::{Duration, SystemTime};
+use tokio::{sync::watch, task::JoinHandle};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Observa a data de modificação do arquivo de armazenamento e avisa quando ela muda.
+///
+/// Escritas feitas pelo próprio processo também disparam o aviso; cabe ao
+/// `TodoStorage::refresh` descobrir se o conteúdo realmente mudou.
+pub struct FileWatcher {
+    receiver: watch::Receiver<Option<SystemTime>>,
+    task: JoinHandle<()>,
+}
+
+impl FileWatcher {
+    pub fn new(path: &str) -> Self {
+        let path = path.to_string();
+        let (sender, receiver) = watch::channel(modified(&path));
+        let task = tokio::spawn(async move {
+            let mut interval = tokio::time::interval(POLL_INTERVAL);
+            loop {
+                interval.tick().await;
+                let current = modified(&path);
+                sender.send_if_modified(|last| {
+                    if *last != current {
+                        *last = current;
+                        true
+                    } else {
+                        false
+                    }
+                });
+            }
+        });
+        Self { receiver, task }
+    }
+
+    /// Aguarda a próxima modificação do arquivo.
+    pub async fn changed(&mut self) -> Result<(), AppError> {
+        self.receiver
+            .changed()
+            .await
+            .map_err(|err| AppError::Read(std::io::Error::other(err)))
+    }
+
+    /// Informa, sem bloquear, se o arquivo mudou desde a última consulta.
+    pub fn has_changed(&mut self) -> bool {
+        let changed = self.receiver.has_changed().unwrap_or(false);
+        self.receiver.borrow_and_update();
+        changed
+    }
+}
+
+impl Drop for FileWatcher {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+fn modified(path: &str) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
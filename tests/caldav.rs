@@ -0,0 +1,392 @@
+//! `CalDavStorage` contra um servidor CalDAV mínimo rodando no próprio processo: ele
+//! guarda os recursos em memória, responde ao REPORT da coleção e confere os ETags
+//! do `If-Match`/`If-None-Match` como um servidor de verdade.
+
+mod common;
+
+use common::ok;
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+};
+use tempfile::TempDir;
+use todo::{
+    caldav::{CalDavConfig, CalDavStorage},
+    cli::AppError,
+    crypto,
+    todo::{TodoStorage, Todos},
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+
+#[derive(Default)]
+struct Collection {
+    /// Conteúdo e versão de cada recurso; a versão é o ETag.
+    resources: BTreeMap<String, (String, u32)>,
+    etags: bool,
+    /// Quantos dos próximos PUTs recebem 412, como se outro cliente tivesse
+    /// alterado o recurso no meio do caminho.
+    rejected_puts: usize,
+    puts: usize,
+}
+
+impl Collection {
+    fn respond(
+        &mut self,
+        method: &str,
+        name: &str,
+        headers: &BTreeMap<String, String>,
+        body: String,
+    ) -> (&'static str, String, String) {
+        let current = self.resources.get(name).map(|(_, version)| *version);
+        let etag = |version: u32, etags: bool| {
+            if etags {
+                format!("ETag: \"{version}\"\r\n")
+            } else {
+                String::new()
+            }
+        };
+        match method {
+            "REPORT" => (
+                "207 Multi-Status",
+                "Content-Type: application/xml\r\n".to_string(),
+                self.multistatus(),
+            ),
+            "GET" => match self.resources.get(name) {
+                Some((data, version)) => ("200 OK", etag(*version, self.etags), data.clone()),
+                None => ("404 Not Found", String::new(), String::new()),
+            },
+            "PUT" => {
+                self.puts += 1;
+                let allowed = match (headers.get("if-match"), headers.get("if-none-match")) {
+                    (Some(expected), _) => {
+                        current.map(|version| format!("\"{version}\"")) == Some(expected.clone())
+                    }
+                    (None, Some(_)) => current.is_none(),
+                    (None, None) => true,
+                };
+                if !allowed || self.rejected_puts > 0 {
+                    self.rejected_puts = self.rejected_puts.saturating_sub(1);
+                    return ("412 Precondition Failed", String::new(), String::new());
+                }
+                let version = current.unwrap_or(0) + 1;
+                self.resources.insert(name.to_string(), (body, version));
+                ("201 Created", etag(version, self.etags), String::new())
+            }
+            "DELETE" => match self.resources.remove(name) {
+                Some(_) => ("204 No Content", String::new(), String::new()),
+                None => ("404 Not Found", String::new(), String::new()),
+            },
+            _ => ("405 Method Not Allowed", String::new(), String::new()),
+        }
+    }
+
+    fn multistatus(&self) -> String {
+        let responses: String = self
+            .resources
+            .iter()
+            .map(|(name, (data, version))| {
+                let etag = if self.etags {
+                    format!("<d:getetag>\"{version}\"</d:getetag>")
+                } else {
+                    String::new()
+                };
+                let data = data
+                    .replace('&', "&amp;")
+                    .replace('<', "&lt;")
+                    .replace('>', "&gt;");
+                format!(
+                    "<d:response><d:href>/cal/{name}</d:href><d:propstat><d:prop>{etag}\
+                     <c:calendar-data>{data}</c:calendar-data></d:prop>\
+                     <d:status>HTTP/1.1 200 OK</d:status></d:propstat></d:response>"
+                )
+            })
+            .collect();
+        format!(
+            "<?xml version=\"1.0\"?>\
+             <d:multistatus xmlns:d=\"DAV:\" xmlns:c=\"urn:ietf:params:xml:ns:caldav\">\
+             {responses}</d:multistatus>"
+        )
+    }
+}
+
+struct Server {
+    url: String,
+    collection: Arc<Mutex<Collection>>,
+}
+
+impl Server {
+    async fn start(etags: bool) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/cal/", listener.local_addr().unwrap());
+        let collection = Arc::new(Mutex::new(Collection {
+            etags,
+            ..Default::default()
+        }));
+        let shared = collection.clone();
+        tokio::spawn(async move {
+            while let Ok((socket, _)) = listener.accept().await {
+                tokio::spawn(handle(socket, shared.clone()));
+            }
+        });
+        Self { url, collection }
+    }
+
+    fn config(&self) -> CalDavConfig {
+        CalDavConfig {
+            url: self.url.clone(),
+            user: None,
+            password: None,
+        }
+    }
+
+    /// Cria ou altera um recurso, como faria outro cliente.
+    fn put(&self, name: &str, data: String) {
+        let mut collection = self.collection.lock().unwrap();
+        let version = collection
+            .resources
+            .get(name)
+            .map_or(0, |(_, version)| *version);
+        collection
+            .resources
+            .insert(name.to_string(), (data, version + 1));
+    }
+
+    fn data(&self, name: &str) -> String {
+        let collection = self.collection.lock().unwrap();
+        collection
+            .resources
+            .get(name)
+            .map(|(data, _)| data.clone())
+            .unwrap_or_default()
+    }
+
+    fn names(&self) -> Vec<String> {
+        let collection = self.collection.lock().unwrap();
+        collection.resources.keys().cloned().collect()
+    }
+
+    fn puts(&self) -> usize {
+        self.collection.lock().unwrap().puts
+    }
+
+    fn reject_puts(&self, count: usize) {
+        self.collection.lock().unwrap().rejected_puts = count;
+    }
+}
+
+/// Atende uma requisição HTTP/1.1 por conexão.
+async fn handle(mut socket: TcpStream, collection: Arc<Mutex<Collection>>) {
+    let mut buffer = Vec::new();
+    let mut chunk = [0; 4096];
+    let head_end = loop {
+        if let Some(end) = buffer.windows(4).position(|window| window == b"\r\n\r\n") {
+            break end;
+        }
+        match socket.read(&mut chunk).await {
+            Ok(0) | Err(_) => return,
+            Ok(read) => buffer.extend_from_slice(&chunk[..read]),
+        }
+    };
+    let head = String::from_utf8_lossy(&buffer[..head_end]).to_string();
+    let mut lines = head.lines();
+    let mut request = lines.next().unwrap_or_default().split_whitespace();
+    let method = request.next().unwrap_or_default().to_string();
+    let path = request.next().unwrap_or_default().to_string();
+    let headers: BTreeMap<String, String> = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim().to_lowercase(), value.trim().to_string()))
+        .collect();
+    let length: usize = headers
+        .get("content-length")
+        .and_then(|length| length.parse().ok())
+        .unwrap_or(0);
+    let mut body = buffer[head_end + 4..].to_vec();
+    while body.len() < length {
+        match socket.read(&mut chunk).await {
+            Ok(0) | Err(_) => return,
+            Ok(read) => body.extend_from_slice(&chunk[..read]),
+        }
+    }
+
+    let name = path.trim_start_matches("/cal/");
+    let (status, extra, response) = collection.lock().unwrap().respond(
+        &method,
+        name,
+        &headers,
+        String::from_utf8_lossy(&body).to_string(),
+    );
+    let reply = format!(
+        "HTTP/1.1 {status}\r\nContent-Length: {}\r\nConnection: close\r\n{extra}\r\n{response}",
+        response.len()
+    );
+    let _ = socket.write_all(reply.as_bytes()).await;
+    let _ = socket.shutdown().await;
+}
+
+fn vtodo(uid: &str, summary: &str) -> String {
+    format!(
+        "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nBEGIN:VTODO\r\nUID:{uid}\r\n\
+         SUMMARY:{summary}\r\nSTATUS:NEEDS-ACTION\r\nEND:VTODO\r\nEND:VCALENDAR\r\n"
+    )
+}
+
+async fn todos(dir: &TempDir) -> Todos {
+    let path = dir.path().join("todo_storage.json");
+    ok(Todos::open(path.to_str().unwrap()).await)
+}
+
+async fn storage(dir: &TempDir, server: &Server) -> CalDavStorage {
+    ok(CalDavStorage::new(todos(dir).await, server.config()).await)
+}
+
+async fn uuid(storage: &CalDavStorage, id: u32) -> String {
+    let list = ok(storage.list().await);
+    let todo = list
+        .iter()
+        .find(|todo| todo.id == id)
+        .expect("TODO não encontrado");
+    todo.uuid.clone()
+}
+
+#[tokio::test]
+async fn new_todos_are_sent_under_their_uuid() {
+    let (dir, server) = (TempDir::new().unwrap(), Server::start(true).await);
+    let mut storage = storage(&dir, &server).await;
+
+    ok(storage.add("Consulta do Zoro".to_string()).await);
+
+    let uuid = uuid(&storage, 1).await;
+    assert_eq!(server.names(), [format!("{uuid}.ics")]);
+    let data = server.data(&format!("{uuid}.ics"));
+    assert!(data.contains(&format!("UID:{uuid}\r\n")));
+    assert!(data.contains("SUMMARY:Consulta do Zoro\r\n"));
+}
+
+#[tokio::test]
+async fn time_tracking_does_not_send_anything() {
+    let (dir, server) = (TempDir::new().unwrap(), Server::start(true).await);
+    let mut storage = storage(&dir, &server).await;
+    ok(storage.add("Consulta do Zoro".to_string()).await);
+    assert_eq!(server.puts(), 1);
+
+    ok(storage.start_timer(1).await);
+    ok(storage.stop_timer().await);
+    ok(storage.complete_pomodoro(1).await);
+    ok(storage.refresh().await);
+    assert_eq!(server.puts(), 1);
+
+    ok(storage
+        .update(1, "Consulta do Zoro às 15h".to_string())
+        .await);
+    assert_eq!(server.puts(), 2);
+}
+
+#[tokio::test]
+async fn changes_are_pulled_from_servers_without_etags() {
+    let (dir, server) = (TempDir::new().unwrap(), Server::start(false).await);
+    let mut storage = storage(&dir, &server).await;
+    ok(storage.add("Consulta do Zoro".to_string()).await);
+    ok(storage.refresh().await);
+
+    let uuid = uuid(&storage, 1).await;
+    server.put(
+        &format!("{uuid}.ics"),
+        vtodo(&uuid, "Consulta do Zoro às 15h"),
+    );
+    assert!(ok(storage.refresh().await));
+    assert_eq!(
+        ok(storage.list().await)[0].message,
+        "Consulta do Zoro às 15h"
+    );
+    assert!(!ok(storage.refresh().await));
+}
+
+#[tokio::test]
+async fn merge_overwrites_a_resource_created_elsewhere() {
+    let (dir, server) = (TempDir::new().unwrap(), Server::start(true).await);
+    let mut todos = todos(&dir).await;
+    let uuid = ok(todos.add("Consulta do Zoro".to_string()).await)
+        .map(|todo| todo.uuid.clone())
+        .unwrap_or_default();
+    // outra cópia da lista já enviou o mesmo TODO
+    server.put(
+        &format!("{uuid}.ics"),
+        vtodo(&uuid, "Consulta do Zoro (cópia)"),
+    );
+
+    let mut storage = ok(CalDavStorage::new(todos, server.config()).await);
+    assert!(matches!(
+        storage
+            .update(1, "Consulta do Zoro às 15h".to_string())
+            .await,
+        Err(AppError::Conflict)
+    ));
+    assert_eq!(ok(storage.merge().await), [1]);
+
+    assert_eq!(server.names(), [format!("{uuid}.ics")]);
+    assert!(server
+        .data(&format!("{uuid}.ics"))
+        .contains("SUMMARY:Consulta do Zoro às 15h\r\n"));
+    ok(storage
+        .update(1, "Consulta do Zoro às 16h".to_string())
+        .await);
+}
+
+#[tokio::test]
+async fn a_conflict_that_repeats_after_merge_is_reported() {
+    let (dir, server) = (TempDir::new().unwrap(), Server::start(true).await);
+    let mut storage = storage(&dir, &server).await;
+    ok(storage.add("Consulta do Zoro".to_string()).await);
+
+    server.reject_puts(usize::MAX);
+    assert!(matches!(
+        storage
+            .update(1, "Consulta do Zoro às 15h".to_string())
+            .await,
+        Err(AppError::Conflict)
+    ));
+    assert_eq!(ok(storage.merge().await), [1]);
+    assert!(matches!(storage.refresh().await, Err(AppError::Conflict)));
+
+    server.reject_puts(0);
+    assert_eq!(ok(storage.merge().await), [1]);
+    ok(storage.refresh().await);
+}
+
+#[tokio::test]
+async fn pulled_todos_keep_their_uid_and_href() {
+    let (dir, server) = (TempDir::new().unwrap(), Server::start(true).await);
+    server.put("conta.ics", vtodo("abc@example.com", "Pagar a conta"));
+    let mut storage = storage(&dir, &server).await;
+    ok(storage.add("Consulta do Zoro".to_string()).await);
+
+    let list = ok(storage.list().await);
+    let id = list
+        .iter()
+        .find(|todo| todo.message == "Pagar a conta")
+        .map(|todo| todo.id)
+        .expect("o TODO do servidor não foi trazido");
+    ok(storage.update(id, "Pagar a conta de luz".to_string()).await);
+
+    assert_eq!(server.names().len(), 2);
+    let data = server.data("conta.ics");
+    assert!(data.contains("UID:abc@example.com\r\n"));
+    assert!(data.contains("SUMMARY:Pagar a conta de luz\r\n"));
+}
+
+#[tokio::test]
+async fn the_state_is_encrypted_with_the_todos() {
+    let (dir, server) = (TempDir::new().unwrap(), Server::start(false).await);
+    let mut storage = storage(&dir, &server).await;
+    ok(storage.add("Consulta do Zoro".to_string()).await);
+
+    ok(storage.set_passphrase(Some("senha".to_string())).await);
+
+    let state = std::fs::read_to_string(dir.path().join("todo_storage.json.caldav")).unwrap();
+    assert!(crypto::is_encrypted(&state));
+    assert!(!state.contains("Zoro"));
+    assert!(ok(storage.encrypted().await));
+}
@@ -0,0 +1,419 @@
+mod common;
+
+use common::{ok, run, text, Input, Output, ScriptedUi};
+use todo::{
+    cli::TodoCli,
+    formats::Format,
+    pomodoro::Phase,
+    terminal::Action,
+    todo::{Bulk, Position, TodoStorage, Todos, DEFAULT_LIST},
+};
+
+fn action(action: Action) -> Input {
+    Input::Action(action)
+}
+
+fn add(message: &str) -> [Input; 2] {
+    [action(Action::Add), text(message)]
+}
+
+fn edit(key: &str, todo_action: Action) -> [Input; 3] {
+    [action(Action::Edit), text(key), action(todo_action)]
+}
+
+fn messages(outputs: &common::Outputs) -> Vec<String> {
+    outputs
+        .last_list()
+        .into_iter()
+        .map(|todo| todo.message)
+        .collect()
+}
+
+#[tokio::test]
+async fn exit_ends_the_session() {
+    let outputs = run([action(Action::Exit)]).await;
+    assert_eq!(outputs.all(), vec![Output::Exit]);
+}
+
+#[tokio::test]
+async fn add_shows_the_new_todo_in_the_list() {
+    let outputs = run(add("Consulta do Zoro")
+        .into_iter()
+        .chain(add("Comprar ração"))
+        .chain([action(Action::List), action(Action::Exit)]))
+    .await;
+
+    let list = outputs.last_list();
+    assert_eq!(messages(&outputs), ["Consulta do Zoro", "Comprar ração"]);
+    assert_eq!(list.iter().map(|todo| todo.id).collect::<Vec<_>>(), [1, 2]);
+    assert!(list.iter().all(|todo| !todo.done && !todo.uuid.is_empty()));
+    assert!(outputs.all().iter().any(|output| matches!(
+        output,
+        Output::Success(todo, msg) if todo.id == 2 && msg == "adicionado com sucesso"
+    )));
+}
+
+#[tokio::test]
+async fn add_rejects_an_empty_todo() {
+    let outputs = run(add("   ")
+        .into_iter()
+        .chain([action(Action::List), action(Action::Exit)]))
+    .await;
+
+    assert!(outputs.last_list().is_empty());
+    assert_eq!(outputs.errors().len(), 1);
+}
+
+#[tokio::test]
+async fn add_asks_before_saving_a_duplicate() {
+    let outputs = run(add("Consulta do Zoro")
+        .into_iter()
+        .chain(add("Consulta  do Zoro"))
+        .chain([Input::Confirm(false)])
+        .chain(add("Consulta do Zoro"))
+        .chain([Input::Confirm(true)])
+        .chain([action(Action::List), action(Action::Exit)]))
+    .await;
+
+    assert_eq!(messages(&outputs), ["Consulta do Zoro", "Consulta do Zoro"]);
+}
+
+#[tokio::test]
+async fn done_marks_the_todo() {
+    let outputs = run(add("Consulta do Zoro")
+        .into_iter()
+        .chain(edit("1", Action::Done(1)))
+        .chain([action(Action::List), action(Action::Exit)]))
+    .await;
+
+    let list = outputs.last_list();
+    assert!(list[0].done);
+    assert!(list[0].completed.is_some());
+    assert!(outputs.all().iter().any(|output| matches!(
+        output,
+        Output::Success(todo, msg) if todo.done && msg == "marcado como feito"
+    )));
+}
+
+#[tokio::test]
+async fn edit_shows_the_selected_todo() {
+    let outputs = run(add("Consulta do Zoro")
+        .into_iter()
+        .chain(edit("1", Action::Exit))
+        .chain([action(Action::Exit)]))
+    .await;
+
+    assert!(outputs.all().iter().any(|output| matches!(
+        output,
+        Output::Todo(todo) if todo.id == 1 && todo.message == "Consulta do Zoro"
+    )));
+}
+
+#[tokio::test]
+async fn edit_reports_an_unknown_id() {
+    let outputs = run(add("Consulta do Zoro")
+        .into_iter()
+        .chain([action(Action::Edit), text("7")])
+        .chain([action(Action::Edit), Input::Cancel])
+        .chain([action(Action::Exit)]))
+    .await;
+
+    assert_eq!(
+        outputs.errors(),
+        [
+            "Não existe um TODO com esse ID",
+            "O ID informado é inválido"
+        ]
+    );
+}
+
+#[tokio::test]
+async fn update_changes_the_text() {
+    let outputs = run(add("Consulta do Zoro")
+        .into_iter()
+        .chain(edit("1", Action::Update(1, "Vacina do Zoro".to_string())))
+        .chain([action(Action::List), action(Action::Exit)]))
+    .await;
+
+    assert_eq!(messages(&outputs), ["Vacina do Zoro"]);
+}
+
+#[tokio::test]
+async fn update_without_changes_keeps_the_todo() {
+    let outputs = run(add("Consulta do Zoro")
+        .into_iter()
+        .chain(edit("1", Action::Update(1, "Consulta do Zoro".to_string())))
+        .chain(edit("1", Action::Update(1, "  ".to_string())))
+        .chain([action(Action::List), action(Action::Exit)]))
+    .await;
+
+    assert_eq!(messages(&outputs), ["Consulta do Zoro"]);
+    let infos = outputs
+        .all()
+        .into_iter()
+        .filter(|output| matches!(output, Output::Info(_)))
+        .count();
+    assert_eq!(infos, 2);
+}
+
+#[tokio::test]
+async fn delete_moves_the_todo_to_the_trash() {
+    let outputs = run(add("Consulta do Zoro")
+        .into_iter()
+        .chain(add("Comprar ração"))
+        .chain(edit("1", Action::Delete(1)))
+        .chain([action(Action::List)])
+        .chain([action(Action::Trash), action(Action::Exit)])
+        .chain([action(Action::Exit)]))
+    .await;
+
+    let lists: Vec<Vec<String>> = outputs
+        .all()
+        .into_iter()
+        .filter_map(|output| match output {
+            Output::List(list) => Some(list.into_iter().map(|todo| todo.message).collect()),
+            _ => None,
+        })
+        .collect();
+    // a listagem principal e depois a da lixeira
+    assert_eq!(lists[lists.len() - 2], ["Comprar ração"]);
+    assert_eq!(lists[lists.len() - 1], ["Consulta do Zoro"]);
+}
+
+#[tokio::test]
+async fn delete_of_a_trashed_todo_fails() {
+    let outputs = run(add("Consulta do Zoro")
+        .into_iter()
+        .chain(edit("1", Action::Delete(1)))
+        .chain([action(Action::Edit), text("1")])
+        .chain([action(Action::Exit)]))
+    .await;
+
+    assert_eq!(outputs.errors(), ["Não existe um TODO com esse ID"]);
+}
+
+#[tokio::test]
+async fn bulk_marks_the_selected_todos() {
+    let outputs = run(add("Consulta do Zoro")
+        .into_iter()
+        .chain(add("Comprar ração"))
+        .chain(add("Vacina do Zoro"))
+        .chain([action(Action::Bulk), text("zoro"), Input::Bulk(Bulk::Done)])
+        .chain([action(Action::List), action(Action::Exit)]))
+    .await;
+
+    let done: Vec<bool> = outputs.last_list().iter().map(|todo| todo.done).collect();
+    assert_eq!(done, [true, false, true]);
+    assert!(outputs
+        .all()
+        .contains(&Output::Info("2 TODOs alterados".to_string())));
+}
+
+#[tokio::test]
+async fn bulk_accepts_a_reversed_range() {
+    let outputs = run(add("Consulta do Zoro")
+        .into_iter()
+        .chain(add("Comprar ração"))
+        .chain(add("Vacina do Zoro"))
+        .chain([action(Action::Bulk), text("3-2"), Input::Bulk(Bulk::Done)])
+        .chain([action(Action::List), action(Action::Exit)]))
+    .await;
+
+    let done: Vec<bool> = outputs.last_list().iter().map(|todo| todo.done).collect();
+    assert_eq!(done, [false, true, true]);
+}
+
+#[tokio::test]
+async fn bulk_tag_applies_the_same_rules_as_typed_text() {
+    let long = "a".repeat(190);
+    let outputs = run(add("Consulta do Zoro")
+        .into_iter()
+        .chain(add(&long))
+        .chain([action(Action::Bulk), text("1-2")])
+        .chain([Input::Bulk(Bulk::Tag("+vet\u{1b}x".to_string()))])
+        .chain([action(Action::Bulk), text("1-2")])
+        .chain([Input::Bulk(Bulk::Tag("+veterinario".to_string()))])
+        .chain([action(Action::List), action(Action::Exit)]))
+    .await;
+
+    assert_eq!(
+        outputs.errors(),
+        [
+            "Tag inválida: a tag não pode ter espaços",
+            "TODO 2: o texto do TODO tem 203 caracteres, o máximo é 200",
+        ]
+    );
+    let messages: Vec<String> = outputs
+        .last_list()
+        .iter()
+        .map(|todo| todo.message.clone())
+        .collect();
+    assert_eq!(messages, ["Consulta do Zoro".to_string(), long]);
+}
+
+#[tokio::test]
+async fn export_writes_the_listed_todos() {
+    let path = std::env::temp_dir().join(format!("todo-cli-{}.txt", std::process::id()));
+    let path = path.to_string_lossy().into_owned();
+    let outputs = run(add("Consulta do Zoro")
+        .into_iter()
+        .chain([action(Action::Export), Input::Format(Format::TodoTxt)])
+        .chain([text(&path), action(Action::Exit)]))
+    .await;
+
+    let contents = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert!(contents.contains("Consulta do Zoro"));
+    assert!(outputs
+        .all()
+        .contains(&Output::Info(format!("1 TODOs exportados para {path}"))));
+}
+
+#[tokio::test]
+async fn starting_a_timer_stops_the_running_one() {
+    let outputs = run(add("Consulta do Zoro +pets")
+        .into_iter()
+        .chain(add("Relatório mensal"))
+        .chain(edit("1", Action::StartTimer(1)))
+        .chain(edit("2", Action::StartTimer(2)))
+        .chain([action(Action::StopTimer), action(Action::StopTimer)])
+        .chain([action(Action::List), action(Action::Exit)]))
+    .await;
+
+    let list = outputs.last_list();
+    assert!(list.iter().all(|todo| !todo.running()));
+    assert_eq!(list[0].time.len(), 1);
+    assert_eq!(list[1].time.len(), 1);
+    assert!(outputs.all().contains(&Output::Info(
+        "O cronômetro do TODO 1 foi parado".to_string()
+    )));
+    assert_eq!(outputs.errors(), ["Nenhum cronômetro está em andamento"]);
+}
+
+#[tokio::test]
+async fn report_sums_the_time_by_todo_tag_and_day() {
+    let today = todo::todo::today();
+    let outputs = run(add("Consulta do Zoro +pets @rua")
+        .into_iter()
+        .chain(edit("1", Action::StartTimer(1)))
+        .chain([action(Action::StopTimer)])
+        .chain([action(Action::Report), Input::Period(today, today)])
+        .chain([action(Action::Exit)]))
+    .await;
+
+    let report = outputs
+        .all()
+        .into_iter()
+        .find_map(|output| match output {
+            Output::Report(report) => Some(report),
+            _ => None,
+        })
+        .expect("o relatório não foi mostrado");
+    assert_eq!(report.todos.len(), 1);
+    assert_eq!(report.todos[0].0, 1);
+    assert_eq!(report.tags.keys().collect::<Vec<_>>(), ["+pets", "@rua"]);
+    assert_eq!(report.days.keys().collect::<Vec<_>>(), [&today]);
+}
+
+#[tokio::test]
+async fn pomodoro_counts_only_the_finished_work_phases() {
+    let outputs = run(add("Consulta do Zoro")
+        .into_iter()
+        .chain(edit("1", Action::Pomodoro(1)))
+        // trabalho, pausa, outro pomodoro interrompido no meio
+        .chain([
+            Input::Confirm(true),
+            Input::Confirm(true),
+            Input::Confirm(true),
+        ])
+        .chain([Input::Confirm(true), Input::Confirm(false)])
+        .chain([action(Action::List), action(Action::Exit)]))
+    .await;
+
+    let list = outputs.last_list();
+    assert_eq!(list[0].pomodoros, 1);
+    // os dois blocos de trabalho entram no controle de horas
+    assert_eq!(list[0].time.len(), 2);
+    assert!(!list[0].running());
+    let phases: Vec<Phase> = outputs
+        .all()
+        .into_iter()
+        .filter_map(|output| match output {
+            Output::Countdown(phase) => Some(phase),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(phases, [Phase::Work, Phase::Break, Phase::Work]);
+    assert!(outputs.all().contains(&Output::Info(
+        "Pomodoro interrompido, ele não foi contado".to_string()
+    )));
+}
+
+#[tokio::test]
+async fn pomodoro_resumes_the_timer_it_paused() {
+    let outputs = run(add("Consulta do Zoro")
+        .into_iter()
+        .chain(add("Relatório mensal"))
+        .chain(edit("2", Action::StartTimer(2)))
+        .chain(edit("1", Action::Pomodoro(1)))
+        // trabalho até o fim, sem pausa
+        .chain([Input::Confirm(true), Input::Confirm(false)])
+        .chain([action(Action::List), action(Action::Exit)]))
+    .await;
+
+    let list = outputs.last_list();
+    assert!(!list[0].running());
+    assert!(list[1].running());
+    assert_eq!(list[1].time.len(), 2);
+    assert!(outputs.all().contains(&Output::Info(
+        "O cronômetro do TODO 2 voltou a contar".to_string()
+    )));
+}
+
+#[tokio::test]
+async fn import_applies_the_same_rules_as_typed_text() {
+    let path = std::env::temp_dir().join(format!("todo-cli-{}.csv", std::process::id()));
+    let path = path.to_string_lossy().into_owned();
+    let long = "x".repeat(201);
+    std::fs::write(
+        &path,
+        format!("message\nConsulta do Zoro\nPagar   a conta\nPagar a conta\n{long}\n"),
+    )
+    .unwrap();
+    let outputs = run(add("Consulta do Zoro")
+        .into_iter()
+        .chain([action(Action::Import), Input::Format(Format::Csv(b','))])
+        .chain([text(&path), Input::Confirm(true)])
+        .chain([action(Action::List), action(Action::Exit)]))
+    .await;
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(messages(&outputs), ["Consulta do Zoro", "Pagar a conta"]);
+    assert_eq!(
+        outputs.errors(),
+        [
+            "Linha 2: já existe um TODO aberto com esse texto (1)",
+            "Linha 4: o texto se repete no arquivo",
+            "Linha 5: o texto do TODO tem 201 caracteres, o máximo é 200",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn reorder_command_finds_todos_in_other_lists() {
+    let mut todos = Todos::in_memory();
+    ok(todos.create_list("casa").await);
+    ok(todos.switch_list("casa").await);
+    ok(todos.add("Lavar a louça".to_string()).await);
+    ok(todos.add("Regar as plantas".to_string()).await);
+    ok(todos.switch_list(DEFAULT_LIST).await);
+    let (ui, outputs) = ScriptedUi::new([]);
+    let mut cli = TodoCli::new(Box::new(ui), Box::new(todos));
+
+    ok(cli.reorder_command("2", Position::Top).await);
+
+    assert_eq!(
+        outputs.all(),
+        [Output::Info("TODO 2 agora está na posição 1".to_string())]
+    );
+}
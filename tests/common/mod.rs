@@ -0,0 +1,322 @@
+//! `UserInterface` roteirizada para dirigir o `TodoCli` nos testes: cada pergunta
+//! consome a próxima resposta da fila e tudo que seria mostrado na tela é gravado
+//! para as verificações.
+
+// cada arquivo de teste usa só uma parte dos utilitários
+#![allow(dead_code)]
+
+use chrono::NaiveDate;
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use todo::{
+    cli::{AppError, TodoCli},
+    formats::{Format, Import},
+    pomodoro::Phase,
+    terminal::{Action, UserInterface},
+    todo::{Bulk, Selection, Todo, TodoList, Todos},
+    tracking::Report,
+};
+
+/// Resposta do usuário a uma pergunta do `TodoCli`.
+pub enum Input {
+    /// Escolha de um menu (`ask_for_action`, `ask_for_todo_action`, ...).
+    Action(Action),
+    /// Texto digitado, também usado como id em `select_todo` e filtro em `select_todos`.
+    Text(String),
+    Confirm(bool),
+    Bulk(Bulk),
+    Format(Format),
+    /// Período do relatório de horas.
+    Period(NaiveDate, NaiveDate),
+    /// Desistência, para as perguntas que aceitam não responder (`Option`).
+    Cancel,
+}
+
+pub fn text(text: &str) -> Input {
+    Input::Text(text.to_string())
+}
+
+/// O valor de um resultado que o teste espera ser `Ok`; `AppError` não é `Debug` e
+/// não funciona com `unwrap`.
+pub fn ok<T>(result: Result<T, AppError>) -> T {
+    result.unwrap_or_else(|err| panic!("{err}"))
+}
+
+/// O que o `TodoCli` mostrou ao usuário, na ordem.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Output {
+    List(Vec<Todo>),
+    Todo(Todo),
+    Success(Todo, String),
+    Error(String),
+    Info(String),
+    Report(Report),
+    Countdown(Phase),
+    Exit,
+}
+
+#[derive(Clone, Default)]
+pub struct Outputs(Arc<Mutex<Vec<Output>>>);
+
+impl Outputs {
+    pub fn all(&self) -> Vec<Output> {
+        self.0.lock().unwrap().clone()
+    }
+
+    /// A última listagem mostrada.
+    pub fn last_list(&self) -> Vec<Todo> {
+        self.all()
+            .into_iter()
+            .rev()
+            .find_map(|output| match output {
+                Output::List(list) => Some(list),
+                _ => None,
+            })
+            .expect("nenhuma listagem foi mostrada")
+    }
+
+    pub fn errors(&self) -> Vec<String> {
+        self.all()
+            .into_iter()
+            .filter_map(|output| match output {
+                Output::Error(msg) => Some(msg),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn push(&self, output: Output) {
+        self.0.lock().unwrap().push(output);
+    }
+}
+
+pub struct ScriptedUi {
+    inputs: VecDeque<Input>,
+    outputs: Outputs,
+}
+
+impl ScriptedUi {
+    pub fn new(inputs: impl IntoIterator<Item = Input>) -> (Self, Outputs) {
+        let outputs = Outputs::default();
+        let ui = Self {
+            inputs: inputs.into_iter().collect(),
+            outputs: outputs.clone(),
+        };
+        (ui, outputs)
+    }
+
+    fn next(&mut self, question: &str) -> Input {
+        self.inputs
+            .pop_front()
+            .unwrap_or_else(|| panic!("o roteiro acabou antes de {question}"))
+    }
+
+    fn action(&mut self, question: &str) -> Action {
+        match self.next(question) {
+            Input::Action(action) => action,
+            _ => panic!("{question} esperava uma Action"),
+        }
+    }
+
+    fn text(&mut self, question: &str) -> String {
+        match self.next(question) {
+            Input::Text(text) => text,
+            _ => panic!("{question} esperava um Text"),
+        }
+    }
+
+    fn optional_text(&mut self, question: &str) -> Option<String> {
+        match self.next(question) {
+            Input::Text(text) => Some(text),
+            Input::Cancel => None,
+            _ => panic!("{question} esperava um Text ou Cancel"),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl UserInterface for ScriptedUi {
+    async fn input(&mut self) -> Result<String, AppError> {
+        Ok(self.text("input"))
+    }
+
+    async fn complete_with(&mut self, _: Vec<&Todo>) -> Result<(), AppError> {
+        Ok(())
+    }
+
+    async fn keep_history(&mut self, _: bool) -> Result<(), AppError> {
+        Ok(())
+    }
+
+    /// Não consome respostas: o roteiro só traz as que mudam o fluxo.
+    async fn press_key(&mut self) -> Result<(), AppError> {
+        Ok(())
+    }
+
+    async fn welcome(&mut self) -> Result<(), AppError> {
+        Ok(())
+    }
+
+    async fn exit(&mut self) -> Result<(), AppError> {
+        self.outputs.push(Output::Exit);
+        Ok(())
+    }
+
+    async fn ask_for_action(&mut self, _: &str, _: Option<&Todo>) -> Result<Action, AppError> {
+        Ok(self.action("ask_for_action"))
+    }
+
+    async fn ask_for_todo_action(&mut self, _: &Todo) -> Result<Action, AppError> {
+        Ok(self.action("ask_for_todo_action"))
+    }
+
+    async fn add_todo(&mut self) -> Result<String, AppError> {
+        Ok(self.text("add_todo"))
+    }
+
+    async fn select_todo(&mut self) -> Result<Option<String>, AppError> {
+        Ok(self.optional_text("select_todo"))
+    }
+
+    async fn show_todo(&mut self, todo: &Todo) -> Result<(), AppError> {
+        self.outputs.push(Output::Todo(todo.clone()));
+        Ok(())
+    }
+
+    async fn edit_notes(&mut self, _: &str) -> Result<String, AppError> {
+        Ok(self.text("edit_notes"))
+    }
+
+    async fn list_todo(&mut self, list: Vec<&Todo>) -> Result<(), AppError> {
+        self.outputs
+            .push(Output::List(list.into_iter().cloned().collect()));
+        Ok(())
+    }
+
+    async fn refresh_todo(&mut self, list: Vec<&Todo>) -> Result<(), AppError> {
+        self.list_todo(list).await
+    }
+
+    async fn show_sucess(&mut self, todo: &Todo, msg: &str) -> Result<(), AppError> {
+        self.outputs
+            .push(Output::Success(todo.clone(), msg.to_string()));
+        Ok(())
+    }
+
+    async fn show_error(&mut self, msg: &str) -> Result<(), AppError> {
+        self.outputs.push(Output::Error(msg.to_string()));
+        Ok(())
+    }
+
+    async fn show_info(&mut self, msg: &str) -> Result<(), AppError> {
+        self.outputs.push(Output::Info(msg.to_string()));
+        Ok(())
+    }
+
+    async fn ask_for_conflict(&mut self) -> Result<Action, AppError> {
+        Ok(self.action("ask_for_conflict"))
+    }
+
+    async fn select_format(&mut self) -> Result<Option<Format>, AppError> {
+        match self.next("select_format") {
+            Input::Format(format) => Ok(Some(format)),
+            Input::Cancel => Ok(None),
+            _ => panic!("select_format esperava um Format ou Cancel"),
+        }
+    }
+
+    async fn ask_for_path(&mut self) -> Result<String, AppError> {
+        Ok(self.text("ask_for_path"))
+    }
+
+    async fn preview_import(&mut self, import: &Import) -> Result<(), AppError> {
+        self.outputs.push(Output::List(import.todos.clone()));
+        for error in &import.errors {
+            self.outputs.push(Output::Error(error.clone()));
+        }
+        Ok(())
+    }
+
+    async fn confirm(&mut self, _: &str) -> Result<bool, AppError> {
+        match self.next("confirm") {
+            Input::Confirm(answer) => Ok(answer),
+            _ => panic!("confirm esperava um Confirm"),
+        }
+    }
+
+    async fn ask_for_list_action(&mut self, _: &[TodoList]) -> Result<Action, AppError> {
+        Ok(self.action("ask_for_list_action"))
+    }
+
+    async fn ask_for_archive_action(
+        &mut self,
+        list: Vec<&Todo>,
+        _: &str,
+    ) -> Result<Action, AppError> {
+        self.list_todo(list).await?;
+        Ok(self.action("ask_for_archive_action"))
+    }
+
+    async fn select_todos(&mut self) -> Result<Option<Selection>, AppError> {
+        Ok(self
+            .optional_text("select_todos")
+            .and_then(|text| Selection::parse(&text)))
+    }
+
+    async fn ask_for_bulk_action(&mut self, _: usize) -> Result<Option<Bulk>, AppError> {
+        match self.next("ask_for_bulk_action") {
+            Input::Bulk(bulk) => Ok(Some(bulk)),
+            Input::Cancel => Ok(None),
+            _ => panic!("ask_for_bulk_action esperava um Bulk ou Cancel"),
+        }
+    }
+
+    async fn ask_for_trash_action(&mut self, list: Vec<&Todo>) -> Result<Action, AppError> {
+        self.list_todo(list).await?;
+        Ok(self.action("ask_for_trash_action"))
+    }
+
+    async fn ask_for_sync_conflict(&mut self, _: &Todo, _: &Todo) -> Result<Action, AppError> {
+        Ok(self.action("ask_for_sync_conflict"))
+    }
+
+    async fn ask_passphrase(&mut self, _: &str) -> Result<String, AppError> {
+        Ok(self.text("ask_passphrase"))
+    }
+
+    async fn ask_for_period(&mut self) -> Result<Option<(NaiveDate, NaiveDate)>, AppError> {
+        match self.next("ask_for_period") {
+            Input::Period(from, to) => Ok(Some((from, to))),
+            Input::Cancel => Ok(None),
+            _ => panic!("ask_for_period esperava um Period ou Cancel"),
+        }
+    }
+
+    async fn show_report(&mut self, report: &Report) -> Result<(), AppError> {
+        self.outputs.push(Output::Report(report.clone()));
+        Ok(())
+    }
+
+    /// Não espera: um `Confirm` diz se a fase chegou ao fim ou foi interrompida.
+    async fn countdown(&mut self, _: &Todo, phase: Phase, _: Duration) -> Result<bool, AppError> {
+        self.outputs.push(Output::Countdown(phase));
+        match self.next("countdown") {
+            Input::Confirm(finished) => Ok(finished),
+            _ => panic!("countdown esperava um Confirm"),
+        }
+    }
+}
+
+/// Roda o `TodoCli` com um armazenamento em memória até o fim do roteiro, que
+/// precisa terminar em `Action::Exit`.
+pub async fn run(inputs: impl IntoIterator<Item = Input>) -> Outputs {
+    let (ui, outputs) = ScriptedUi::new(inputs);
+    let mut cli = TodoCli::new(Box::new(ui), Box::new(Todos::in_memory()));
+    if let Err(err) = cli.run().await {
+        panic!("o TodoCli terminou com erro: {err}");
+    }
+    outputs
+}
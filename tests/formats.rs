@@ -0,0 +1,249 @@
+use chrono::NaiveDate;
+use todo::{
+    formats::{markdown::Group, Format, Import},
+    todo::Todo,
+};
+
+fn date(value: &str) -> Option<NaiveDate> {
+    todo::todo::parse_date(value)
+}
+
+fn import(format: Format, contents: &str, existing: &[&Todo]) -> Import {
+    format
+        .import(contents, existing)
+        .unwrap_or_else(|err| panic!("{err}"))
+}
+
+#[test]
+fn csv_import_keeps_the_fields_without_a_column() {
+    let existing = Todo {
+        id: 1,
+        message: "Consulta do Zoro".to_string(),
+        done: true,
+        created: date("2023-02-01"),
+        completed: date("2023-02-15"),
+        ..Default::default()
+    };
+    let import = import(
+        Format::Csv(b','),
+        "id,message\n1,Consulta do Zoro +pets\n",
+        &[&existing],
+    );
+
+    assert!(import.errors.is_empty());
+    let todo = &import.todos[0];
+    assert_eq!(todo.message, "Consulta do Zoro +pets");
+    assert!(todo.done);
+    assert_eq!(todo.created, date("2023-02-01"));
+    assert_eq!(todo.completed, date("2023-02-15"));
+}
+
+#[test]
+fn taskwarrior_round_trip_keeps_the_project_order_and_foreign_metadata() {
+    let mut todo = Todo {
+        id: 1,
+        message: "Revisar +casa orçamento +obra @rua".to_string(),
+        created: date("2023-02-01"),
+        ..Default::default()
+    };
+    for (key, value) in [
+        ("uid", "abc@example.com"),
+        ("t", "2023-02-20"),
+        ("scheduled", "20230301T000000Z"),
+        ("color", "blue"),
+        ("taskwarrior", "scheduled,color"),
+    ] {
+        todo.metadata.insert(key.to_string(), value.to_string());
+    }
+    let exported = Format::Taskwarrior.export(&[&todo]).unwrap_or_default();
+    assert!(exported.contains("\"description\": \"Revisar orçamento +obra\""));
+    assert!(exported.contains("\"color\": \"blue\""));
+    // só o que veio do Taskwarrior volta para ele
+    for key in ["uid", "t", "taskwarrior"] {
+        assert!(!exported.contains(&format!("\"{key}\"")), "{key}");
+    }
+
+    // o agendamento foi tirado no Taskwarrior
+    let exported = exported.replace("\"scheduled\": \"20230301T000000Z\",", "");
+    let import = import(Format::Taskwarrior, &exported, &[&todo]);
+
+    assert!(import.errors.is_empty());
+    let imported = &import.todos[0];
+    assert_eq!(imported.message, "Revisar orçamento +casa +obra @rua");
+    assert_eq!(imported.projects(), todo.projects());
+    let metadata = |key: &str| imported.metadata.get(key).map(String::as_str);
+    assert_eq!(metadata("color"), Some("blue"));
+    assert_eq!(metadata("uid"), Some("abc@example.com"));
+    assert_eq!(metadata("t"), Some("2023-02-20"));
+    assert_eq!(metadata("scheduled"), None);
+    assert_eq!(metadata("taskwarrior"), Some("color"));
+}
+
+#[test]
+fn todotxt_reimport_updates_the_existing_todos() {
+    let mut first = Todo {
+        id: 1,
+        message: "Consulta do Zoro +pets".to_string(),
+        created: date("2023-02-01"),
+        notes: "levar a carteirinha".to_string(),
+        ..Default::default()
+    };
+    first
+        .metadata
+        .insert("due".to_string(), "2023-02-20".to_string());
+    let second = Todo {
+        id: 2,
+        message: "Pagar a conta".to_string(),
+        done: true,
+        created: date("2023-02-03"),
+        ..Default::default()
+    };
+    let exported = Format::TodoTxt
+        .export(&[&first, &second])
+        .unwrap_or_default();
+    assert_eq!(
+        exported,
+        "2023-02-01 Consulta do Zoro +pets due:2023-02-20\n\
+         x 2023-02-03 2023-02-03 Pagar a conta\n"
+    );
+
+    let import = import(Format::TodoTxt, &exported, &[&first, &second]);
+    assert_eq!(import.todos.len(), 2);
+    assert_eq!(import.todos[0], first);
+    assert_eq!(import.todos[1].id, 2);
+    assert_eq!(import.todos[1].created, date("2023-02-03"));
+}
+
+#[test]
+fn todotxt_keeps_unknown_key_values_in_the_message() {
+    let import = import(
+        Format::TodoTxt,
+        "(A) Ligar re:orçamento às 14:30 due:2023-03-01\n",
+        &[],
+    );
+    let todo = &import.todos[0];
+    assert_eq!(todo.message, "Ligar re:orçamento às 14:30");
+    assert_eq!(todo.priority, Some('A'));
+    assert_eq!(
+        todo.metadata.get("due").map(String::as_str),
+        Some("2023-03-01")
+    );
+}
+
+#[test]
+fn ical_uses_the_uuid_as_uid() {
+    let todo = Todo {
+        id: 1,
+        uuid: "6f0c5d1e-2b7a-4c51-9a63-0d8e4f2b1a77".to_string(),
+        message: "Consulta do Zoro".to_string(),
+        ..Default::default()
+    };
+    let exported = Format::ICalendar.export(&[&todo]).unwrap_or_default();
+    assert!(exported.contains("UID:6f0c5d1e-2b7a-4c51-9a63-0d8e4f2b1a77\r\n"));
+
+    // outra cópia da lista, onde o mesmo TODO tem outro id curto
+    let copy = Todo {
+        id: 7,
+        ..todo.clone()
+    };
+    let other = Todo {
+        id: 1,
+        message: "Relatório mensal".to_string(),
+        ..Default::default()
+    };
+    let matched = import(Format::ICalendar, &exported, &[&other, &copy]);
+    assert_eq!(matched.todos[0].id, 7);
+    assert!(!matched.todos[0].metadata.contains_key("uid"));
+
+    // sem TODO correspondente, o uuid vem do UID
+    let new = import(Format::ICalendar, &exported, &[&other]);
+    assert_eq!(new.todos[0].id, 0);
+    assert_eq!(new.todos[0].uuid, todo.uuid);
+}
+
+#[test]
+fn ical_keeps_foreign_uids_in_the_metadata() {
+    let contents = "BEGIN:VCALENDAR\r\nBEGIN:VTODO\r\nUID:abc@example.com\r\n\
+                    SUMMARY:Pagar a conta\r\nEND:VTODO\r\nEND:VCALENDAR\r\n";
+    let imported = import(Format::ICalendar, contents, &[]).todos.remove(0);
+    assert_eq!(
+        imported.metadata.get("uid").map(String::as_str),
+        Some("abc@example.com")
+    );
+
+    let existing = Todo { id: 3, ..imported };
+    let matched = import(Format::ICalendar, contents, &[&existing]);
+    assert_eq!(matched.todos[0].id, 3);
+    let exported = Format::ICalendar.export(&[&existing]).unwrap_or_default();
+    assert!(exported.contains("UID:abc@example.com\r\n"));
+}
+
+#[test]
+fn markdown_reimport_matches_the_todos_by_id() {
+    let todo = Todo {
+        id: 7,
+        message: "Consulta do Zoro".to_string(),
+        created: date("2023-02-01"),
+        ..Default::default()
+    };
+    let exported = Format::Markdown(Group::None)
+        .export(&[&todo])
+        .unwrap_or_default();
+
+    // o texto mudou no arquivo, mas o marcador continua
+    let edited = exported.replace("[ ] Consulta do Zoro", "[x] Consulta do Zoro às 10h");
+    let import = import(Format::Markdown(Group::None), &edited, &[&todo]);
+
+    assert!(import.errors.is_empty());
+    assert_eq!(import.todos.len(), 1);
+    let imported = &import.todos[0];
+    assert_eq!(imported.id, 7);
+    assert_eq!(imported.message, "Consulta do Zoro às 10h");
+    assert!(imported.done);
+    assert_eq!(imported.created, date("2023-02-01"));
+}
+
+#[test]
+fn markdown_grouped_export_imports_each_todo_once() {
+    let both = Todo {
+        id: 1,
+        message: "Orçamento +casa +obra".to_string(),
+        ..Default::default()
+    };
+    let single = Todo {
+        id: 2,
+        message: "Pintar a parede +obra".to_string(),
+        ..Default::default()
+    };
+    let exported = Format::Markdown(Group::Project)
+        .export(&[&both, &single])
+        .unwrap_or_default();
+    assert!(exported.contains("## +casa"));
+    assert!(exported.contains("## +obra"));
+
+    let import = import(
+        Format::Markdown(Group::Project),
+        &exported,
+        &[&both, &single],
+    );
+    let ids: Vec<u32> = import.todos.iter().map(|todo| todo.id).collect();
+    assert_eq!(ids, [1, 2]);
+    assert_eq!(import.sources.len(), 2);
+}
+
+#[test]
+fn markdown_notes_with_list_markers_stay_notes() {
+    let todo = Todo {
+        id: 1,
+        message: "Mudança".to_string(),
+        notes: "Levar:\n- [ ] caixas\n  * fita\n\\ barra".to_string(),
+        ..Default::default()
+    };
+    let exported = Format::Markdown(Group::None)
+        .export(&[&todo])
+        .unwrap_or_default();
+
+    let import = import(Format::Markdown(Group::None), &exported, &[&todo]);
+    assert_eq!(import.todos.len(), 1);
+    assert_eq!(import.todos[0].notes, todo.notes);
+}
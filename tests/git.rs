@@ -0,0 +1,110 @@
+//! `GitStorage` contra um repositório bare num diretório temporário, no papel do
+//! remoto de `TODO_GIT_REMOTE`.
+
+mod common;
+
+use common::ok;
+use std::process::Command;
+use tempfile::TempDir;
+use todo::{
+    git::{GitConfig, GitRepo, GitStorage},
+    todo::{TodoStorage, Todos},
+};
+
+fn git(dir: &str, args: &[&str]) -> String {
+    let output = Command::new("git")
+        .current_dir(dir)
+        .args(args)
+        .output()
+        .expect("git não encontrado");
+    assert!(output.status.success(), "git {args:?} falhou");
+    String::from_utf8(output.stdout).unwrap()
+}
+
+/// Um remoto bare vazio.
+fn remote() -> TempDir {
+    let remote = TempDir::new().unwrap();
+    git(
+        remote.path().to_str().unwrap(),
+        &["init", "--quiet", "--bare"],
+    );
+    remote
+}
+
+fn config(dir: &TempDir, remote: &TempDir) -> GitConfig {
+    GitConfig {
+        dir: dir.path().join("repo").to_string_lossy().into_owned(),
+        remote: Some(remote.path().to_string_lossy().into_owned()),
+    }
+}
+
+async fn storage(config: &GitConfig) -> GitStorage {
+    let repo = ok(GitRepo::open(config).await);
+    let todos = ok(Todos::open(&config.path()).await).pretty();
+    GitStorage::new(Box::new(todos), repo)
+}
+
+fn log(remote: &TempDir) -> Vec<String> {
+    git(remote.path().to_str().unwrap(), &["log", "--format=%s"])
+        .lines()
+        .map(str::to_string)
+        .collect()
+}
+
+#[tokio::test]
+async fn each_change_is_a_commit_sent_to_the_remote() {
+    let (dir, remote) = (TempDir::new().unwrap(), remote());
+    let mut storage = storage(&config(&dir, &remote)).await;
+
+    ok(storage.add("Consulta do Zoro".to_string()).await);
+    ok(storage.done(1).await);
+
+    assert_eq!(
+        log(&remote),
+        ["done #1: Consulta do Zoro", "add #1: Consulta do Zoro"]
+    );
+}
+
+#[tokio::test]
+async fn the_file_is_written_one_field_per_line() {
+    let (dir, remote) = (TempDir::new().unwrap(), remote());
+    let config = config(&dir, &remote);
+    let mut storage = storage(&config).await;
+
+    ok(storage.add("Consulta do Zoro".to_string()).await);
+    ok(storage.done(1).await);
+
+    // o diff do done mostra só os campos que mudaram, não o TODO inteiro
+    let diff = git(&config.dir, &["diff", "--unified=0", "HEAD~1"]);
+    assert!(diff.contains("\n+      \"done\": true,\n"), "{diff}");
+    assert!(!diff.contains("Consulta do Zoro"), "{diff}");
+}
+
+#[tokio::test]
+async fn another_clone_pulls_the_changes() {
+    let (first, second, remote) = (TempDir::new().unwrap(), TempDir::new().unwrap(), remote());
+    let mut storage = storage(&config(&first, &remote)).await;
+    ok(storage.add("Consulta do Zoro".to_string()).await);
+
+    let storage = self::storage(&config(&second, &remote)).await;
+
+    let list = ok(storage.list().await);
+    assert_eq!(list.len(), 1);
+    assert_eq!(list[0].message, "Consulta do Zoro");
+}
+
+#[tokio::test]
+async fn commits_of_an_encrypted_file_leave_the_text_out() {
+    let (dir, remote) = (TempDir::new().unwrap(), remote());
+    let mut storage = storage(&config(&dir, &remote)).await;
+    ok(storage.create_list("clientes").await);
+    ok(storage.set_passphrase(Some("senha".to_string())).await);
+
+    ok(storage.add("Consulta do Zoro".to_string()).await);
+    ok(storage.create_list("fornecedores").await);
+
+    assert_eq!(
+        log(&remote)[..3],
+        ["list: cria", "add #1", "encrypt: troca a senha do arquivo"]
+    );
+}
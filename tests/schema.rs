@@ -0,0 +1,86 @@
+//! Leitura dos formatos antigos do arquivo de TODOs, a partir de arquivos gravados
+//! por versões anteriores em `tests/fixtures`.
+
+mod common;
+
+use common::ok;
+use std::fs;
+use tempfile::TempDir;
+use todo::{
+    cli::AppError,
+    schema::{self, VERSION},
+    todo::{TodoStorage, Todos, DEFAULT_LIST},
+};
+
+fn fixture(name: &str) -> String {
+    fs::read_to_string(format!("tests/fixtures/{name}")).unwrap()
+}
+
+#[test]
+fn reads_the_original_tuple() {
+    let (sequence, todos, lists) = ok(schema::read(&fixture("v1.json")));
+
+    assert_eq!(sequence, 2);
+    assert_eq!(todos.len(), 2);
+    assert_eq!(todos[&2].message, "Comprar ração +pet");
+    assert!(todos[&2].done);
+    assert_eq!(todos[&1].list, DEFAULT_LIST);
+    assert!(lists.is_empty());
+    // o uuid é derivado do conteúdo: duas leituras chegam ao mesmo valor
+    assert_eq!(todos[&1].uuid.len(), 36);
+    assert_ne!(todos[&1].uuid, todos[&2].uuid);
+    let (_, again, _) = ok(schema::read(&fixture("v1.json")));
+    assert_eq!(todos[&1].uuid, again[&1].uuid);
+}
+
+#[test]
+fn reads_the_tuple_with_lists() {
+    let (sequence, todos, lists) = ok(schema::read(&fixture("v2.json")));
+
+    assert_eq!(sequence, 3);
+    assert_eq!(todos[&3].list, "trabalho");
+    assert_eq!(lists["trabalho"].order, [3]);
+    // o uuid guardado nos metadados por uma importação do Taskwarrior é mantido
+    assert_eq!(todos[&3].uuid, "6c4b1e3a-8f2d-4c71-9b0a-2f6a1d9e5c44");
+    assert!(!todos[&3].metadata.contains_key("uuid"));
+    assert_eq!(todos[&3].metadata["project"], "clientes");
+    assert_eq!(todos[&1].uuid.len(), 36);
+}
+
+#[test]
+fn refuses_a_newer_version() {
+    let result = schema::read(&fixture("future.json"));
+
+    assert!(matches!(result, Err(AppError::NewerVersion(99))));
+}
+
+#[test]
+fn writes_what_it_reads() {
+    let (sequence, todos, lists) = ok(schema::read(&fixture("v2.json")));
+
+    let contents = ok(schema::write(sequence, &todos, &lists, false));
+
+    assert!(contents.starts_with(&format!("{{\"version\":{VERSION},")));
+    let (sequence_read, todos_read, lists_read) = ok(schema::read(&contents));
+    assert_eq!(sequence_read, sequence);
+    assert_eq!(todos_read, todos);
+    assert!(lists_read == lists);
+}
+
+#[tokio::test]
+async fn an_old_file_is_rewritten_in_the_current_format() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("todo_storage.json");
+    fs::write(&path, fixture("v1.json")).unwrap();
+    let path = path.to_str().unwrap();
+
+    let mut todos = ok(Todos::open(path).await);
+    let uuid = ok(todos.find("1").await).unwrap().uuid.clone();
+    ok(todos.add("Pagar a conta".to_string()).await);
+
+    let contents = fs::read_to_string(path).unwrap();
+    assert!(contents.starts_with(&format!("{{\"version\":{VERSION},")));
+    let (sequence, saved, _) = ok(schema::read(&contents));
+    assert_eq!(sequence, 3);
+    assert_eq!(saved[&1].uuid, uuid);
+}
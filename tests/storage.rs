@@ -0,0 +1,131 @@
+//! `Todos` usado diretamente pelo `TodoStorage`, sem o `TodoCli`.
+
+mod common;
+
+use common::ok;
+use tempfile::TempDir;
+use todo::{
+    cli::AppError,
+    todo::{Position, TodoStorage, Todos, DEFAULT_LIST},
+};
+
+async fn ids(todos: &Todos) -> Vec<u32> {
+    ok(todos.list().await).iter().map(|todo| todo.id).collect()
+}
+
+#[tokio::test]
+async fn reorder_keeps_the_place_of_todos_in_the_trash() {
+    let mut todos = Todos::in_memory();
+    for message in ["um", "dois", "três", "quatro"] {
+        ok(todos.add(message.to_string()).await);
+    }
+    ok(todos.reorder(4, Position::Bottom).await);
+    ok(todos.delete(2).await);
+
+    ok(todos.reorder(4, Position::Top).await);
+    assert_eq!(ids(&todos).await, [4, 1, 3]);
+
+    ok(todos.untrash("2").await);
+    assert_eq!(ids(&todos).await, [4, 1, 2, 3]);
+}
+
+/// Dois processos com o mesmo arquivo aberto.
+async fn two_processes(dir: &TempDir) -> (Todos, Todos) {
+    let path = dir.path().join("todo_storage.json");
+    let path = path.to_str().unwrap();
+    let mut first = ok(Todos::open(path).await);
+    ok(first.add("Consulta do Zoro".to_string()).await);
+    ok(first.create_list("casa").await);
+    (first, ok(Todos::open(path).await))
+}
+
+fn conflict<T>(result: Result<T, AppError>) {
+    assert!(matches!(result, Err(AppError::Conflict)));
+}
+
+#[tokio::test]
+async fn merge_keeps_the_local_side_of_a_todo_edited_by_both() {
+    let dir = TempDir::new().unwrap();
+    let (mut first, mut second) = two_processes(&dir).await;
+    ok(first.update(1, "Consulta do Zoro às 15h".to_string()).await);
+    let theirs = first.get(1).unwrap().modified;
+
+    conflict(
+        second
+            .update(1, "Consulta do Zoro às 17h".to_string())
+            .await,
+    );
+    assert_eq!(ok(second.merge().await), [1]);
+
+    let todo = second.get(1).unwrap();
+    assert_eq!(todo.message, "Consulta do Zoro às 17h");
+    // a alteração local ganha uma data nova, usada depois pelo sync
+    assert!(todo.modified > theirs);
+}
+
+#[tokio::test]
+async fn merge_keeps_a_todo_edited_on_one_side_and_deleted_on_the_other() {
+    let dir = TempDir::new().unwrap();
+    let (mut first, mut second) = two_processes(&dir).await;
+    ok(first.delete(1).await);
+    ok(first.empty_trash().await);
+
+    conflict(second.done(1).await);
+    assert_eq!(ok(second.merge().await), [1]);
+
+    assert!(second.get(1).unwrap().done);
+}
+
+#[tokio::test]
+async fn merge_keeps_todos_added_by_both() {
+    let dir = TempDir::new().unwrap();
+    let (mut first, mut second) = two_processes(&dir).await;
+    ok(first.add("Pagar a conta".to_string()).await);
+
+    conflict(second.add("Comprar ração".to_string()).await);
+    assert!(ok(second.merge().await).is_empty());
+
+    let messages: Vec<&str> = second
+        .all()
+        .iter()
+        .map(|todo| todo.message.as_str())
+        .collect();
+    assert_eq!(
+        messages,
+        ["Consulta do Zoro", "Pagar a conta", "Comprar ração"]
+    );
+}
+
+#[tokio::test]
+async fn merge_joins_changes_to_the_lists() {
+    let dir = TempDir::new().unwrap();
+    let (mut first, mut second) = two_processes(&dir).await;
+    ok(first.rename_list("casa", "lar").await);
+    ok(first.archive_list("lar", true).await);
+
+    conflict(second.reorder(1, Position::Top).await);
+    ok(second.merge().await);
+
+    let lists = ok(second.lists().await);
+    let names: Vec<(&str, bool)> = lists
+        .iter()
+        .map(|list| (list.name.as_str(), list.archived))
+        .collect();
+    assert_eq!(names, [(DEFAULT_LIST, false), ("lar", true)]);
+    assert_eq!(second.lists_info()[DEFAULT_LIST].order, [1]);
+}
+
+#[tokio::test]
+async fn deleting_a_list_sends_its_todos_to_the_trash() {
+    let mut todos = Todos::in_memory();
+    ok(todos.create_list("casa").await);
+    ok(todos.switch_list("casa").await);
+    ok(todos.add("Lavar a louça".to_string()).await);
+
+    assert_eq!(ok(todos.delete_list("casa").await), Some(1));
+
+    assert_eq!(ok(todos.current_list().await), DEFAULT_LIST);
+    assert_eq!(ok(todos.trash().await).len(), 1);
+    ok(todos.untrash("1").await);
+    assert_eq!(ids(&todos).await, [1]);
+}
@@ -0,0 +1,190 @@
+//! `todo sync` entre dois arquivos num diretório temporário, com as respostas aos
+//! conflitos vindas da `ScriptedUi`.
+
+mod common;
+
+use common::{ok, Input, Output, ScriptedUi};
+use std::fs;
+use tempfile::TempDir;
+use todo::{
+    cli::AppError,
+    sync,
+    terminal::Action,
+    todo::{Todo, TodoStorage, Todos},
+};
+
+struct Files {
+    dir: TempDir,
+}
+
+impl Files {
+    fn new() -> Self {
+        Self {
+            dir: TempDir::new().unwrap(),
+        }
+    }
+
+    fn path(&self, name: &str) -> String {
+        self.dir.path().join(name).to_string_lossy().into_owned()
+    }
+
+    async fn local(&self) -> Todos {
+        ok(Todos::open(&self.path("todo_storage.json")).await)
+    }
+
+    async fn other(&self) -> Todos {
+        ok(Todos::open(&self.path("shared.json")).await)
+    }
+
+    /// Sincroniza os dois arquivos respondendo aos conflitos com `answers`.
+    async fn sync(&self, answers: impl IntoIterator<Item = Action>) -> Result<(), AppError> {
+        let (mut ui, outputs) = ScriptedUi::new(answers.into_iter().map(Input::Action));
+        let (mut local, mut other) = (self.local().await, self.other().await);
+        sync::sync(&mut ui, &mut local, &mut other).await?;
+        assert!(matches!(outputs.all().last(), Some(Output::Info(_))));
+        Ok(())
+    }
+}
+
+fn messages(todos: &Todos) -> Vec<String> {
+    todos
+        .all()
+        .into_iter()
+        .map(|todo| todo.message.clone())
+        .collect()
+}
+
+fn first(todos: &Todos) -> Todo {
+    todos.all()[0].clone()
+}
+
+#[tokio::test]
+async fn todos_of_each_side_go_to_the_other() {
+    let files = Files::new();
+    ok(files
+        .local()
+        .await
+        .add("Consulta do Zoro".to_string())
+        .await);
+    ok(files.other().await.add("Pagar a conta".to_string()).await);
+
+    ok(files.sync([]).await);
+
+    let (local, other) = (files.local().await, files.other().await);
+    assert_eq!(messages(&local), ["Consulta do Zoro", "Pagar a conta"]);
+    assert_eq!(messages(&other), messages(&local));
+}
+
+#[tokio::test]
+async fn without_a_base_the_newest_change_wins() {
+    let files = Files::new();
+    ok(files
+        .local()
+        .await
+        .add("Consulta do Zoro".to_string())
+        .await);
+    // uma cópia do arquivo, com os mesmos uuids, mas sem nenhum sync anterior
+    fs::copy(files.path("todo_storage.json"), files.path("shared.json")).unwrap();
+    ok(files
+        .local()
+        .await
+        .update(1, "Consulta do Zoro às 15h".to_string())
+        .await);
+
+    ok(files.sync([]).await);
+
+    assert_eq!(messages(&files.other().await), ["Consulta do Zoro às 15h"]);
+}
+
+#[tokio::test]
+async fn a_todo_deleted_on_one_side_is_deleted_on_both() {
+    let files = Files::new();
+    ok(files
+        .local()
+        .await
+        .add("Consulta do Zoro".to_string())
+        .await);
+    ok(files.sync([]).await);
+
+    let mut other = files.other().await;
+    ok(other.delete(1).await);
+    ok(other.empty_trash().await);
+    ok(files.sync([]).await);
+
+    assert!(files.local().await.all().is_empty());
+    assert!(files.other().await.all().is_empty());
+}
+
+#[tokio::test]
+async fn edits_to_different_fields_are_merged() {
+    let files = Files::new();
+    ok(files
+        .local()
+        .await
+        .add("Consulta do Zoro".to_string())
+        .await);
+    ok(files.sync([]).await);
+
+    ok(files
+        .local()
+        .await
+        .update(1, "Consulta do Zoro às 15h".to_string())
+        .await);
+    ok(files.other().await.done(1).await);
+    ok(files.sync([]).await);
+
+    for todos in [files.local().await, files.other().await] {
+        let todo = first(&todos);
+        assert_eq!(todo.message, "Consulta do Zoro às 15h");
+        assert!(todo.done);
+    }
+}
+
+#[tokio::test]
+async fn edits_to_the_same_field_ask_the_user() {
+    let files = Files::new();
+    ok(files
+        .local()
+        .await
+        .add("Consulta do Zoro".to_string())
+        .await);
+    ok(files.sync([]).await);
+
+    ok(files
+        .local()
+        .await
+        .update(1, "Consulta do Zoro às 15h".to_string())
+        .await);
+    ok(files
+        .other()
+        .await
+        .update(1, "Consulta do Zoro às 17h".to_string())
+        .await);
+    ok(files.sync([Action::KeepOther]).await);
+
+    assert_eq!(messages(&files.local().await), ["Consulta do Zoro às 17h"]);
+    assert_eq!(messages(&files.other().await), ["Consulta do Zoro às 17h"]);
+}
+
+#[tokio::test]
+async fn a_failure_on_the_other_file_leaves_the_local_one_untouched() {
+    let files = Files::new();
+    ok(files
+        .local()
+        .await
+        .add("Consulta do Zoro".to_string())
+        .await);
+    ok(files.other().await.add("Pagar a conta".to_string()).await);
+    let before = fs::read_to_string(files.path("todo_storage.json")).unwrap();
+
+    let (mut ui, _) = ScriptedUi::new([]);
+    let (mut local, mut other) = (files.local().await, files.other().await);
+    // outro processo altera o arquivo compartilhado no meio do sync
+    ok(files.other().await.add("Comprar ração".to_string()).await);
+    let result = sync::sync(&mut ui, &mut local, &mut other).await;
+
+    assert!(matches!(result, Err(AppError::Conflict)));
+    let after = fs::read_to_string(files.path("todo_storage.json")).unwrap();
+    assert_eq!(after, before);
+    assert!(!fs::exists(files.path("todo_storage.json.sync")).unwrap());
+}